@@ -2,132 +2,2893 @@
 #![no_std]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[doc(hidden)]
+pub mod __private {
+	#[cfg(feature = "alloc")]
+	pub use alloc::{borrow::Cow, string::String};
+	#[cfg(feature = "anyhow")]
+	pub use anyhow;
+	#[cfg(feature = "num-traits")]
+	pub use num_traits;
+	#[cfg(feature = "std")]
+	pub use std::panic::{AssertUnwindSafe, catch_unwind};
+
+	/// Lets the closure passed to the `peek_then` stage infer its argument type.
+	pub fn peek_then<I: Iterator, F: FnOnce(Option<&I::Item>)>(
+		iter: &mut core::iter::Peekable<I>,
+		f: F,
+	) {
+		f(iter.peek())
+	}
+
+	/// Restricts `pipe_array!` to arrays, which would otherwise accept anything with a `map` method.
+	pub fn array_map<T, U, const N: usize>(array: [T; N], f: impl FnMut(T) -> U) -> [U; N] {
+		array.map(f)
+	}
+
+	/// Compares against `T::default()` for the `skip_if_default` stage, without needing the type to be inferred.
+	pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+		*value == T::default()
+	}
+}
+
 /// A macro which evaluates functions from left to right, rather than from inside to outside.
 ///
 /// Syntax: `pipe!(init => fn1 => fn2 => ...)`
 ///
-/// Each function is either the name of a single-argument function (optionally with parentheses),
-/// an expression which is parenthesizable and callable as a single-argument function (usually a lambda),
+/// The initial value can be any expression, including a block with its own `let` bindings and control flow. It can also
+/// be named as `(name = init)`, in which case `name` can be referred to by any of the functions. There is no implicit
+/// name such as `input` for it: hygiene keeps names bound inside the macro from being visible to the functions you pass
+/// in, so the name has to come from you.
+///
+/// Each function is either the name of a single-argument function (optionally with empty parentheses, as in `fn1()`,
+/// `u32::count_ones()` or `y.method()`), an expression which is parenthesizable and callable as a single-argument
+/// function (usually a lambda, which may also be parenthesized with empty parentheses, as in `(f)()`),
 /// or a name/parenthesized expression followed by a parenthesized comma-separated list of arguments with one or more
 /// arguments left as blank (`_`). All function calls and expressions to the left will be evaluated, stored in a temporary,
-/// and then inserted into the current function call in place of any blanks.
+/// and then inserted into the current function call in place of any blanks. A blank may also be borrowed (`&_`), or be an
+/// element of an array literal (`&[_, y]`) or an argument of a macro invocation (`vec![_]`) within an argument. Names may
+/// be followed by a turbofish (`fn1::<T>`, `y.method::<T>(_, z)`), in which case any `_` inside the turbofish is left as
+/// an inferred type. Since the output is only known at runtime, `_` can't be used inside braced const generic arguments
+/// (`fn1::<{ _ }>`). Names may also be `::`-separated paths, such as `u32::max(_, y)` or `crate::util::transform`.
+/// A function may also be a macro invocation with blanks among its arguments, including as named arguments, e.g.
+/// `format!("value is {v}", v = _)`.
+///
+/// When the output is a tuple, positional blanks `_0` to `_9` can be used instead of `_` to insert its fields, e.g.
+/// `pipe!(x => split3 => combine(_2, _0, _1))`. These may also be borrowed or nested like `_`.
+///
+/// A function may also be written as a method call on a blank receiver (`_.method(...)`), in which case everything after
+/// the `_` is applied to the output of the functions to the left, e.g. `_.parse::<i64>()`, `_.collect::<Vec<_>>()` or
+/// `_.send(msg).await`. To call a trait method which shares its name with an inherent method, the receiver can be
+/// qualified as in `<_ as Trait>::method(args)`, which passes the output as the receiver before `args`. Since the
+/// blank receiver form is applied as written, methods need their parentheses (`_.len()`), while
+/// `_.field` accesses a field.
+///
+/// Several functions can be grouped in parentheses, as in `pipe!(x => (fn1 => fn2) => fn3)`, which behaves exactly the
+/// same as without the parentheses.
+///
+/// Any function can be wrapped as `(name = fn1)` to also bind its output to `name`, which stays in scope for the rest of
+/// the pipeline. Later functions can then refer to it in their arguments, e.g. borrow it with `fn2(_, &name)`.
+///
+/// A function can be prefixed with `catch`, as in `catch fn1`, to run it inside [`catch_unwind`], so that its output
+/// becomes `Ok(output)` if it returns normally and `Err(payload)` if it panics. The stage is wrapped in
+/// [`AssertUnwindSafe`], so the usual [`UnwindSafe`] check is skipped on your behalf: if it panics while mutating
+/// something it borrowed, that value may be left in an inconsistent state. Requires the `std` feature, without which
+/// `catch` is rejected with a compile error. `catch` is only treated as a modifier when another function follows it, so
+/// a function which is itself named `catch` can still be piped into as `catch` or `catch(_, y)`.
+///
+/// [`catch_unwind`]: https://doc.rust-lang.org/std/panic/fn.catch_unwind.html
+/// [`AssertUnwindSafe`]: https://doc.rust-lang.org/std/panic/struct.AssertUnwindSafe.html
+/// [`UnwindSafe`]: https://doc.rust-lang.org/std/panic/trait.UnwindSafe.html
+///
+/// The output can also be destructured with `let pattern`, such as `let (a, b)`. The bound names stay in scope for the
+/// rest of the pipeline, and the function after the `let` is instead an expression (usually using those names) which
+/// becomes the new value passed on, e.g. `pipe!(x => split => let (a, b) => combine(b, a) => fn3)`.
+///
+/// A function preceded by a `#[cfg(...)]` attribute is skipped when the configuration predicate is false, in which case
+/// the output of the functions to the left is passed straight to the next function.
+///
+/// Some names are keyword stages, which call the method of the same name on the output of the functions to the left
+/// rather than a function with that name:
+///
+/// - `reduce(f)`: [`Iterator::reduce`] with the two-argument closure `f`.
+/// - `ok`, `err`: [`Result::ok`] and [`Result::err`].
+/// - `chunks(n)`, `windows(n)`: [`slice::chunks`] and [`slice::windows`].
+/// - `clone`, `to_owned`: [`Clone::clone`] and `ToOwned::to_owned`.
+/// - `get_or_insert(value)`, `get_or_insert_with(f)`: [`Option::get_or_insert`] and [`Option::get_or_insert_with`],
+///   usually on a `&mut Option<T>`.
+/// - `clamp(lo, hi)`, `min(other)`, `max(other)`: [`Ord::clamp`], [`Ord::min`] and [`Ord::max`] (or the inherent
+///   methods of the same name on floats).
+/// - `context(msg)`, `with_context(f)`: `anyhow::Context::context` and `anyhow::Context::with_context`. Requires the
+///   `anyhow` feature, without which they are called like any other function.
+/// - `collect_string`: [`Iterator::collect`] into a `String`. Requires the `alloc` feature, without which it is called
+///   like any other function.
+/// - `find(f)`, `position(f)`: [`Iterator::find`] and [`Iterator::position`].
+/// - `split(sep)`, `split_whitespace`, `lines`: [`str::split`], [`str::split_whitespace`] and [`str::lines`].
+/// - `apply_all(stages)`: applies each function in the iterable `stages` (such as `&[fn(T) -> T]`) in order.
+/// - `unwrap`, `expect(msg)`: [`Result::unwrap`] and [`Result::expect`] (or the [`Option`] methods of the same name).
+/// - `extract(pattern => value, else default)`: `value` if the output matches `pattern` (whose bindings `value` can use),
+///   and `default` otherwise.
+/// - `push_mut(value)`, `insert_mut(index, value)`: calls `push`/`insert` on the output (such as a `Vec`) and passes on
+///   the mutated output.
+/// - `scale(factor)`, `offset(delta)`: multiplies the output by `factor` or adds `delta` to it.
+/// - `then(f)`, `then_some(value)`: [`bool::then`] and [`bool::then_some`].
+/// - `count`, `sum`, `product`: [`Iterator::count`], [`Iterator::sum`] and [`Iterator::product`]. The latter two accept
+///   a turbofish, as in `sum::<u64>()`.
+/// - `to_cow`, `into_owned`: converts the output into a `Cow` with `Cow::from` (borrowing references such as `&str` and
+///   owning values such as `String`), and `Cow::into_owned`. `to_cow` requires the `alloc` feature, without which it is
+///   called like any other function.
+/// - `peekable`: [`Iterator::peekable`].
+/// - `peek_then(f)`: calls `f` with the result of [`Peekable::peek`](core::iter::Peekable::peek) on the output, then
+///   passes on the output without having consumed anything.
+/// - `saturating_add(rhs)`, `saturating_sub(rhs)`, `saturating_mul(rhs)`: the integer methods of the same name, such as
+///   [`u32::saturating_add`].
+/// - `record(buffer)`: pushes a clone of the output onto `buffer` (such as `&mut Vec<T>`), then passes on the output.
+/// - `and(other)`, `or(other)`: [`Result::and`] and [`Result::or`] (or the [`Option`] methods of the same name).
+/// - `copied`, `cloned`: [`Iterator::copied`] and [`Iterator::cloned`] (or the [`Option`] methods of the same name).
+/// - `map_or(default, f)`, `map_or_else(default, f)`: [`Result::map_or`] and [`Result::map_or_else`] (or the [`Option`]
+///   methods of the same name).
+/// - `scan(init, f)`: [`Iterator::scan`].
+/// - `skip_if_default(fallback)`: if the output equals its type's [`Default`] value, skips the rest of the functions and
+///   evaluates to `fallback` instead.
+/// - `append f(args)`: calls `f` with the output appended as its last argument, as in `f(args, _)`.
+/// - `try_into`, `try_from::<T>`: [`TryInto::try_into`] and [`TryFrom::try_from`]. Either may be followed by `?` to
+///   propagate the error from the enclosing function.
+/// - `inspect_each(f)`: [`Iterator::inspect`], which calls `f` on each element as it is consumed.
+/// - `chain(other)`: [`Iterator::chain`].
+/// - `map(f)`: [`Iterator::map`] (or the [`Option`] and [`Result`] methods of the same name). `f` can be any function,
+///   including a constructor such as `Some` or `Enum::Variant`.
+/// - `push_str_mut(s)`, `push_char_mut(c)`: calls `String::push_str` or `String::push` on the output and passes on
+///   the mutated output. Requires the `alloc` feature, without which they are called like any other function.
+/// - `try_fold(init, f)`: [`Iterator::try_fold`].
+/// - `max_by_key(f)`, `min_by_key(f)`, `max_by(cmp)`, `min_by(cmp)`: [`Iterator::max_by_key`],
+///   [`Iterator::min_by_key`], [`Iterator::max_by`] and [`Iterator::min_by`].
+/// - `borrow_rest`: passes a reference to the output on to the rest of the functions. When it directly follows an initial
+///   value which is a variable, as in `pipe!(v => borrow_rest => ...)`, `v` isn't moved and stays usable afterwards.
+/// - `filter_opt(pred)`: [`Option::filter`], which turns a `Some` whose value fails `pred` into `None`. Unlike
+///   [`Iterator::filter`], this consumes an `Option` rather than an iterator.
+/// - `cast::<T>`: `num_traits::cast`, which converts the output into the numeric type `T`, or `None` if it is out of
+///   range. Requires the `num-traits` feature, without which `cast::<T>` is called like any other function.
 #[macro_export]
 macro_rules! pipe {
+	// Arms which need to bind the output do so with `match $in { pipe_temp => ... }` rather than `let pipe_temp = $in;`,
+	// since `match` keeps any temporaries borrowed by `$in` alive for the rest of the pipeline, while a `let` in a block
+	// drops them at the end of its statement.
+	// Collects the tokens of the current stage up to the next `=>`, then hands them to `@$callback`. Up to four tokens
+	// are taken at a time, since every step adds to the recursion depth of the rest of the pipeline.
+	(@split [$($callback:tt)*] [$($stage:tt)*] => $($tail:tt)+) => {
+		$crate::pipe!(@$($callback)* [$($stage)*] => $($tail)+)
+	};
+	(@split [$($callback:tt)*] [$($stage:tt)*] $a:tt => $($tail:tt)+) => {
+		$crate::pipe!(@$($callback)* [$($stage)* $a] => $($tail)+)
+	};
+	(@split [$($callback:tt)*] [$($stage:tt)*] $a:tt $b:tt => $($tail:tt)+) => {
+		$crate::pipe!(@$($callback)* [$($stage)* $a $b] => $($tail)+)
+	};
+	(@split [$($callback:tt)*] [$($stage:tt)*] $a:tt $b:tt $c:tt => $($tail:tt)+) => {
+		$crate::pipe!(@$($callback)* [$($stage)* $a $b $c] => $($tail)+)
+	};
+	(@split [$($callback:tt)*] [$($stage:tt)*] $a:tt $b:tt $c:tt $d:tt $($tail:tt)*) => {
+		$crate::pipe!(@split [$($callback)*] [$($stage)* $a $b $c $d] $($tail)*)
+	};
+	(@split [$($callback:tt)*] [$($stage:tt)*] $($rest:tt)*) => {
+		$crate::pipe!(@$($callback)* [$($stage)* $($rest)*])
+	};
+	(@receiver $in:expr, [$($stage:tt)*] $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in $($stage)* $(=> $($tail)+)?)
+	};
+	// Replaces blanks in an argument list with `$temp`, descending into array literals and macro arguments. The state
+	// is `[[callee]] $temp [found] [output] [stack of enclosing lists] remaining tokens`, where `macro` may follow the
+	// callee when it is a macro. This expands to the call alone: the arms which start it bind the output to `$temp` and
+	// pass the call on to the rest of the pipeline, so that the recursion needed to walk the arguments of one stage
+	// doesn't add up over the stages of a long pipeline.
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _ $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp] $stack $($rest)*)
+	};
+	// Positional blanks, which each take one field of a tuple output
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _0 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.0] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _1 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.1] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _2 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.2] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _3 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.3] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _4 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.4] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _5 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.5] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _6 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.6] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _7 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.7] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _8 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.8] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt _9 $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp [found] [$($out)* $temp.9] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt & $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [$($out)* &] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] [$($stack:tt)*] [$($inner:tt)*] $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [] [[bracket [$($out)*] [$($rest)*]] $($stack)*] $($inner)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] [$($stack:tt)*] $($m:ident)::+ ! [$($inner:tt)*] $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [] [[bracket [$($out)* $($m)::+ !] [$($rest)*]] $($stack)*] $($inner)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] [$($stack:tt)*] $($m:ident)::+ ! ($($inner:tt)*) $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [] [[paren [$($out)* $($m)::+ !] [$($rest)*]] $($stack)*] $($inner)*)
+	};
+	// Named arguments, as in `format!("{v}", v = _)`
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt $name:ident = $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [$($out)* $name =] $stack $($rest)*)
+	};
+	// Arguments of a macro are copied token by token, since it may not accept them as opaque expressions
+	(@fill [$callee:tt macro] $temp:ident $found:tt [$($out:tt)*] $stack:tt $next:tt $($rest:tt)*) => {
+		$crate::pipe!(@fill [$callee macro] $temp $found [$($out)* $next] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt $arg:expr_2021 , $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [$($out)* $arg,] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt $arg:expr_2021) => {
+		$crate::pipe!(@fill $k $temp $found [$($out)* $arg] $stack)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] $stack:tt $next:tt $($rest:tt)*) => {
+		$crate::pipe!(@fill $k $temp $found [$($out)* $next] $stack $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] [[bracket [$($outer:tt)*] [$($rest:tt)*]] $($stack:tt)*]) => {
+		$crate::pipe!(@fill $k $temp $found [$($outer)* [$($out)*]] [$($stack)*] $($rest)*)
+	};
+	(@fill $k:tt $temp:ident $found:tt [$($out:tt)*] [[paren [$($outer:tt)*] [$($rest:tt)*]] $($stack:tt)*]) => {
+		$crate::pipe!(@fill $k $temp $found [$($outer)* ($($out)*)] [$($stack)*] $($rest)*)
+	};
+	(@fill [[$($callee:tt)*] $($mode:ident)?] $temp:ident [found] [$($out:tt)*] []) => {
+		$($callee)*($($out)*)
+	};
+	(@fill [[$($callee:tt)*] $($mode:ident)?] $temp:ident [] [$($out:tt)*] []) => {
+		$($callee)*($($out)*)($temp)
+	};
+	(@cfg $in:expr, [$($pred:tt)*] [$($stage:tt)*] $(=> $($tail:tt)+)?) => {
+		match $in {
+			pipe_temp => {
+				#[cfg($($pred)*)]
+				let pipe_temp = $crate::pipe!(pipe_temp => $($stage)*);
+				$crate::pipe!(pipe_temp $(=> $($tail)+)?)
+			}
+		}
+	};
+	(@catch $in:expr, [$($stage:tt)*] $(=> $($tail:tt)+)?) => {
+		match $in {
+			pipe_temp => $crate::pipe!(
+				$crate::__private::catch_unwind($crate::__private::AssertUnwindSafe(
+					|| $crate::pipe!(pipe_temp => $($stage)*)
+				)) $(=> $($tail)+)?
+			),
+		}
+	};
+	// Rejects blanks inside braced const arguments, then calls the stage like the arms for a list of types
+	(@turbofish $in:expr, [$($stage:tt)*] $($tail:tt)*) => {
+		match $in {
+			pipe_temp => $crate::pipe!($crate::pipe!(@turbofish_scan [pipe_temp, [$($stage)*]] $($stage)*) $($tail)*),
+		}
+	};
+	(@turbofish_scan $k:tt { $($inner:tt)* } $($rest:tt)*) => {
+		$crate::pipe!(@turbofish_const $k [$($rest)*] $($inner)*)
+	};
+	(@turbofish_scan $k:tt $next:tt $($rest:tt)*) => {
+		$crate::pipe!(@turbofish_scan $k $($rest)*)
+	};
+	(@turbofish_scan [$temp:ident, [$($stage:tt)*]]) => {
+		$crate::pipe!(@turbofish_call [$temp, []] $($stage)*)
+	};
+	(@turbofish_const $k:tt $rest:tt _ $($inner:tt)*) => {
+		::core::compile_error!(
+			"blanks (`_`) can't be used in const generic arguments, since the piped value is only known at runtime"
+		)
+	};
+	(@turbofish_const $k:tt $rest:tt $next:tt $($inner:tt)*) => {
+		$crate::pipe!(@turbofish_const $k $rest $($inner)*)
+	};
+	(@turbofish_const $k:tt [$($rest:tt)*]) => {
+		$crate::pipe!(@turbofish_scan $k $($rest)*)
+	};
+	// Splits off the argument list, if any, like the stage arms for a list of types
+	(@turbofish_call [$temp:ident, $callee:tt] ($($args:tt)+)) => {
+		$crate::pipe!(@fill [$callee] $temp [] [] [] $($args)+)
+	};
+	(@turbofish_call [$temp:ident, [$($callee:tt)*]] $(())?) => {
+		$($callee)*($temp)
+	};
+	(@turbofish_call [$temp:ident, [$($callee:tt)*]] $next:tt $($rest:tt)*) => {
+		$crate::pipe!(@turbofish_call [$temp, [$($callee)* $next]] $($rest)*)
+	};
+	// Splices a parenthesized group of functions into the pipeline if it contains `=>`, and otherwise calls it as an
+	// expression
+	(@group [$in:expr, [$($group:tt)+], [$($tail:tt)*]] => $($rest:tt)*) => {
+		$crate::pipe!($in => $($group)+ $($tail)*)
+	};
+	(@group $k:tt $next:tt $($rest:tt)*) => {
+		$crate::pipe!(@group $k $($rest)*)
+	};
+	(@group [$in:expr, [$($group:tt)+], [$($tail:tt)*]]) => {
+		$crate::pipe!(($($group)+)($in) $($tail)*)
+	};
 	($e:expr) => { $e };
-	($in:expr => $($i:ident).+ $(())? $(=> $($tail:tt)+)?) => {
-		pipe!($($i).+($in) $(=> $($tail)+)?)
+	(($name:ident = $in:expr) => $($tail:tt)+) => {
+		match $in {
+			$name => $crate::pipe!($name => $($tail)+),
+		}
+	};
+	($in:expr => #[cfg($($pred:tt)*)] $($rest:tt)+) => {
+		$crate::pipe!(@split [cfg $in, [$($pred)*]] [] $($rest)+)
+	};
+	// Method calls and fields on a blank receiver are applied one at a time, without first splitting off the stage
+	($in:expr => _ . $m:ident $(::<$($gen:ty),+>)? ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.$m $(::<$($gen),+>)? ($($args)*) $(=> $($tail)+)?)
+	};
+	($in:expr => _ . $m:ident $(::<$($gen:ty),+>)? ($($args:tt)*) . $($rest:tt)+) => {
+		$crate::pipe!($in.$m $(::<$($gen),+>)? ($($args)*) => _ . $($rest)+)
+	};
+	($in:expr => _ . $field:ident $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.$field $(=> $($tail)+)?)
+	};
+	($in:expr => _ . $field:ident . $($rest:tt)+) => {
+		$crate::pipe!($in.$field => _ . $($rest)+)
+	};
+	($in:expr => _ . $($rest:tt)+) => {
+		$crate::pipe!(@split [receiver $in,] [.] $($rest)+)
+	};
+	// A function named `catch`, rather than the modifier
+	($in:expr => catch $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(catch($in) $(=> $($tail)+)?)
+	};
+	($in:expr => catch ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[catch]] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => catch $($rest:tt)+) => {
+		$crate::__pipe_std!(
+			[$crate::pipe!(@split [catch $in,] [] $($rest)+)]
+			[::core::compile_error!("the `catch` modifier requires the `std` feature of `pipelining-macro`")]
+		)
+	};
+	($in:expr => ($name:ident = $($stage:tt)+) $(=> $($tail:tt)+)?) => {
+		match $crate::pipe!($in => $($stage)+) {
+			$name => $crate::pipe!($name $(=> $($tail)+)?),
+		}
+	};
+	($in:expr => let $pat:pat => $next:expr $(=> $($tail:tt)+)?) => {
+		match $in {
+			$pat => $crate::pipe!($next $(=> $($tail)+)?),
+		}
+	};
+	($in:expr => reduce($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.reduce($f) $(=> $($tail)+)?)
+	};
+	($in:expr => ok $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.ok() $(=> $($tail)+)?)
+	};
+	($in:expr => err $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.err() $(=> $($tail)+)?)
+	};
+	($in:expr => chunks($n:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.chunks($n) $(=> $($tail)+)?)
+	};
+	($in:expr => windows($n:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.windows($n) $(=> $($tail)+)?)
+	};
+	($in:expr => clone $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.clone() $(=> $($tail)+)?)
+	};
+	($in:expr => to_owned $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.to_owned() $(=> $($tail)+)?)
+	};
+	($in:expr => get_or_insert($value:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.get_or_insert($value) $(=> $($tail)+)?)
+	};
+	($in:expr => get_or_insert_with($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.get_or_insert_with($f) $(=> $($tail)+)?)
+	};
+	($in:expr => clamp($lo:expr_2021, $hi:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.clamp($lo, $hi) $(=> $($tail)+)?)
+	};
+	($in:expr => min($other:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.min($other) $(=> $($tail)+)?)
+	};
+	($in:expr => max($other:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.max($other) $(=> $($tail)+)?)
+	};
+	($in:expr => context($msg:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_anyhow!(
+			[$crate::pipe!($crate::__private::anyhow::Context::context($in, $msg) $(=> $($tail)+)?)]
+			[$crate::pipe!(context($msg)($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => with_context($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_anyhow!(
+			[$crate::pipe!($crate::__private::anyhow::Context::with_context($in, $f) $(=> $($tail)+)?)]
+			[$crate::pipe!(with_context($f)($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => collect_string $(())? $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_alloc!(
+			[$crate::pipe!($in.collect::<$crate::__private::String>() $(=> $($tail)+)?)]
+			[$crate::pipe!(collect_string($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => find($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.find($f) $(=> $($tail)+)?)
+	};
+	($in:expr => position($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.position($f) $(=> $($tail)+)?)
+	};
+	($in:expr => split($sep:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.split($sep) $(=> $($tail)+)?)
+	};
+	($in:expr => split_whitespace $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.split_whitespace() $(=> $($tail)+)?)
+	};
+	($in:expr => lines $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.lines() $(=> $($tail)+)?)
+	};
+	($in:expr => apply_all($stages:expr_2021) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval the input before the functions
+			pipe_temp => $crate::pipe!(
+				::core::iter::IntoIterator::into_iter($stages).fold(pipe_temp, |pipe_acc, pipe_fn| pipe_fn(pipe_acc))
+				$(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => unwrap $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.unwrap() $(=> $($tail)+)?)
+	};
+	($in:expr => expect($msg:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.expect($msg) $(=> $($tail)+)?)
+	};
+	($in:expr => extract($pat:pat => $value:expr_2021, else $default:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(if let $pat = $in { $value } else { $default } $(=> $($tail)+)?)
+	};
+	($in:expr => push_mut($value:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(match $in {
+			mut pipe_temp => {
+				pipe_temp.push($value);
+				pipe_temp
+			}
+		} $(=> $($tail)+)?)
+	};
+	($in:expr => insert_mut($index:expr_2021, $value:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(match $in {
+			mut pipe_temp => {
+				pipe_temp.insert($index, $value);
+				pipe_temp
+			}
+		} $(=> $($tail)+)?)
+	};
+	($in:expr => scale($factor:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in * $factor $(=> $($tail)+)?)
+	};
+	($in:expr => offset($delta:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in + $delta $(=> $($tail)+)?)
+	};
+	($in:expr => then($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.then($f) $(=> $($tail)+)?)
+	};
+	($in:expr => then_some($value:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.then_some($value) $(=> $($tail)+)?)
+	};
+	($in:expr => count $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.count() $(=> $($tail)+)?)
+	};
+	($in:expr => sum $(::<$t:ty>)? $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.sum$(::<$t>)?() $(=> $($tail)+)?)
+	};
+	($in:expr => product $(::<$t:ty>)? $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.product$(::<$t>)?() $(=> $($tail)+)?)
+	};
+	($in:expr => to_cow $(())? $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_alloc!(
+			[$crate::pipe!($crate::__private::Cow::from($in) $(=> $($tail)+)?)]
+			[$crate::pipe!(to_cow($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => into_owned $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.into_owned() $(=> $($tail)+)?)
+	};
+	($in:expr => peekable $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.peekable() $(=> $($tail)+)?)
+	};
+	($in:expr => peek_then($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(match $in {
+			mut pipe_temp => {
+				$crate::__private::peek_then(&mut pipe_temp, $f);
+				pipe_temp
+			}
+		} $(=> $($tail)+)?)
+	};
+	($in:expr => saturating_add($rhs:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.saturating_add($rhs) $(=> $($tail)+)?)
+	};
+	($in:expr => saturating_sub($rhs:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.saturating_sub($rhs) $(=> $($tail)+)?)
+	};
+	($in:expr => saturating_mul($rhs:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.saturating_mul($rhs) $(=> $($tail)+)?)
+	};
+	($in:expr => record($buf:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(match $in {
+			pipe_temp => {
+				$buf.push(::core::clone::Clone::clone(&pipe_temp));
+				pipe_temp
+			}
+		} $(=> $($tail)+)?)
+	};
+	($in:expr => and($other:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.and($other) $(=> $($tail)+)?)
+	};
+	($in:expr => or($other:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.or($other) $(=> $($tail)+)?)
 	};
-	($in:expr => $($i:ident).+ ($($($arg_head:expr_2021),*,)? $(_ $(, $arg_tail:expr_2021)*),*) $(=> $($tail:tt)+)?) => {
+	($in:expr => copied $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.copied() $(=> $($tail)+)?)
+	};
+	($in:expr => cloned $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.cloned() $(=> $($tail)+)?)
+	};
+	($in:expr => map_or($default:expr_2021, $f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.map_or($default, $f) $(=> $($tail)+)?)
+	};
+	($in:expr => map_or_else($default:expr_2021, $f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.map_or_else($default, $f) $(=> $($tail)+)?)
+	};
+	($in:expr => scan($init:expr_2021, $f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.scan($init, $f) $(=> $($tail)+)?)
+	};
+	($in:expr => skip_if_default($fallback:expr_2021) $(=> $($tail:tt)+)?) => {
+		match $in {
+			pipe_temp => 'pipe_skip: {
+				if $crate::__private::is_default(&pipe_temp) {
+					break 'pipe_skip $fallback;
+				}
+				$crate::pipe!(pipe_temp $(=> $($tail)+)?)
+			}
+		}
+	};
+	($in:expr => append $($i:ident).+ $(::<$($gen:ty),+>)? ($($($args:tt)+)?) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in => $($i).+ $(::<$($gen),+>)? ($($($args)+,)? _) $(=> $($tail)+)?)
+	};
+	($in:expr => append $($seg:ident)::+ $(::<$($gen:ty),+>)? ($($($args:tt)+)?) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in => $($seg)::+ $(::<$($gen),+>)? ($($($args)+,)? _) $(=> $($tail)+)?)
+	};
+	($in:expr => try_into $(())? ? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(::core::convert::TryInto::try_into($in)? $(=> $($tail)+)?)
+	};
+	($in:expr => try_into $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(::core::convert::TryInto::try_into($in) $(=> $($tail)+)?)
+	};
+	($in:expr => try_from::<$t:ty> $(())? ? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(<$t as ::core::convert::TryFrom<_>>::try_from($in)? $(=> $($tail)+)?)
+	};
+	($in:expr => try_from::<$t:ty> $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(<$t as ::core::convert::TryFrom<_>>::try_from($in) $(=> $($tail)+)?)
+	};
+	($in:expr => inspect_each($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.inspect($f) $(=> $($tail)+)?)
+	};
+	($in:expr => chain($other:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.chain($other) $(=> $($tail)+)?)
+	};
+	($in:expr => map($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.map($f) $(=> $($tail)+)?)
+	};
+	($in:expr => push_str_mut($s:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_alloc!(
+			[$crate::pipe!(match $in {
+				mut pipe_temp => {
+					$crate::__private::String::push_str(&mut pipe_temp, $s);
+					pipe_temp
+				}
+			} $(=> $($tail)+)?)]
+			[$crate::pipe!(push_str_mut($s)($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => push_char_mut($c:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_alloc!(
+			[$crate::pipe!(match $in {
+				mut pipe_temp => {
+					$crate::__private::String::push(&mut pipe_temp, $c);
+					pipe_temp
+				}
+			} $(=> $($tail)+)?)]
+			[$crate::pipe!(push_char_mut($c)($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => try_fold($init:expr_2021, $f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.try_fold($init, $f) $(=> $($tail)+)?)
+	};
+	($in:expr => max_by_key($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.max_by_key($f) $(=> $($tail)+)?)
+	};
+	($in:expr => min_by_key($f:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.min_by_key($f) $(=> $($tail)+)?)
+	};
+	($in:expr => max_by($cmp:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.max_by($cmp) $(=> $($tail)+)?)
+	};
+	($in:expr => min_by($cmp:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($in.min_by($cmp) $(=> $($tail)+)?)
+	};
+	($in:expr => borrow_rest $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(&$in $(=> $($tail)+)?)
+	};
+	($in:expr => filter_opt($pred:expr_2021) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(::core::option::Option::filter($in, $pred) $(=> $($tail)+)?)
+	};
+	($in:expr => cast::<$t:ty> $(())? $(=> $($tail:tt)+)?) => {
+		$crate::__pipe_num_traits!(
+			[$crate::pipe!($crate::__private::num_traits::cast::<_, $t>($in) $(=> $($tail)+)?)]
+			[$crate::pipe!(cast::<$t>($in) $(=> $($tail)+)?)]
+		)
+	};
+	($in:expr => $($i:ident).+ $(::<$($gen:ty),+>)? $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($($i).+ $(::<$($gen),+>)? ($in) $(=> $($tail)+)?)
+	};
+	($in:expr => $($i:ident).+ $(::<$($gen:ty),+>)? ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[$($i).+ $(::<$($gen),+>)?]] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => $($seg:ident)::+ $(::<$($gen:ty),+>)? $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($($seg)::+ $(::<$($gen),+>)? ($in) $(=> $($tail)+)?)
+	};
+	($in:expr => $($seg:ident)::+ $(::<$($gen:ty),+>)? ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[$($seg)::+ $(::<$($gen),+>)?]] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => :: $($seg:ident)::+ $(::<$($gen:ty),+>)? $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(:: $($seg)::+ $(::<$($gen),+>)? ($in) $(=> $($tail)+)?)
+	};
+	($in:expr => :: $($seg:ident)::+ $(::<$($gen:ty),+>)? ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[:: $($seg)::+ $(::<$($gen),+>)?]] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => <_ as $tr:path>::$m:ident $(::<$($gen:ty),+>)? $(())? $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(<_ as $tr>::$m $(::<$($gen),+>)? ($in) $(=> $($tail)+)?)
+	};
+	($in:expr => <_ as $tr:path>::$m:ident $(::<$($gen:ty),+>)? ($($args:tt)+) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[<_ as $tr>::$m $(::<$($gen),+>)?]] pipe_temp [] [] [] _, $($args)+) $(=> $($tail)+)?
+			),
+		}
+	};
+	// Turbofish which aren't a list of types, such as const generic arguments
+	($in:expr => $($i:ident).+ ::< $($rest:tt)+) => {
+		$crate::pipe!(@split [turbofish $in,] [] $($i).+ ::< $($rest)+)
+	};
+	($in:expr => $($seg:ident)::+ ::< $($rest:tt)+) => {
+		$crate::pipe!(@split [turbofish $in,] [] $($seg)::+ ::< $($rest)+)
+	};
+	($in:expr => $($m:ident)::+ ! ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[$($m)::+ !] macro] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => $($m:ident)::+ ! [$($args:tt)*] $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[$($m)::+ !] macro] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => ($e:expr) () $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($e($in) $(=> $($tail)+)?)
+	};
+	($in:expr => ($e:expr) ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => $crate::pipe!(
+				$crate::pipe!(@fill [[$e]] pipe_temp [] [] [] $($args)*) $(=> $($tail)+)?
+			),
+		}
+	};
+	($in:expr => ($($group:tt)+) $(=> $($tail:tt)+)?) => {
+		$crate::pipe!(@group [$in, [$($group)+], [$(=> $($tail)+)?]] $($group)+)
+	};
+	($in:expr => $e:expr $(=> $($tail:tt)+)?) => {
+		$crate::pipe!($e($in) $(=> $($tail)+)?)
+	};
+}
+
+// Keyword stages which need an optional feature expand to the first list of tokens if it is enabled, and otherwise to the
+// second, which calls the stage like any other function. This has to be decided here, since a `cfg` in the output of
+// `pipe!` would check the features of the calling crate.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "alloc")]
+macro_rules! __pipe_alloc {
+	([$($on:tt)*] [$($off:tt)*]) => { $($on)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "alloc"))]
+macro_rules! __pipe_alloc {
+	([$($on:tt)*] [$($off:tt)*]) => { $($off)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! __pipe_std {
+	([$($on:tt)*] [$($off:tt)*]) => { $($on)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "std"))]
+macro_rules! __pipe_std {
+	([$($on:tt)*] [$($off:tt)*]) => { $($off)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "anyhow")]
+macro_rules! __pipe_anyhow {
+	([$($on:tt)*] [$($off:tt)*]) => { $($on)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "anyhow"))]
+macro_rules! __pipe_anyhow {
+	([$($on:tt)*] [$($off:tt)*]) => { $($off)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "num-traits")]
+macro_rules! __pipe_num_traits {
+	([$($on:tt)*] [$($off:tt)*]) => { $($on)* };
+}
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "num-traits"))]
+macro_rules! __pipe_num_traits {
+	([$($on:tt)*] [$($off:tt)*]) => { $($off)* };
+}
+
+/// A variant of [`pipe!`] where the output of every function is a [`Result`] which is unwrapped before being passed on.
+///
+/// Syntax: `pipe_try!(init => fn1 => fn2 => ...)`
+///
+/// Each function accepts the same forms as in [`pipe!`], except for those which bind names for later functions: named
+/// initial values and intermediates (`(name = fn1)`) and `let` destructuring aren't supported, since every function is
+/// piped into separately. If any of them returns an `Err`, the remaining functions are skipped and the whole macro
+/// evaluates to that `Err`. Otherwise it evaluates to `Ok` of the output of the last function. A function disabled with
+/// `#[cfg]` passes the previous output on unchanged, and `borrow_rest` passes on a reference to it without unwrapping
+/// anything. A group of functions in parentheses (`(fn1 => fn2)`) is piped into as a whole, so only its final output is
+/// unwrapped.
+///
+/// This behaves like a `try` block rather than like `?`: it never returns from the enclosing function, and errors are
+/// passed through as-is rather than being converted with [`From`], so every function in the pipeline must share the same
+/// error type.
+#[macro_export]
+macro_rules! pipe_try {
+	// Each function is taken off the front of the pipeline and bound to `$temp` as its own statement, so the depth of
+	// the expansion doesn't pile up with every function. Its tokens are collected up to four at a time like in `pipe!`.
+	(@split $label:lifetime, $temp:ident, [$($stage:tt)+] => $($tail:tt)+) => {
+		$crate::pipe_try!(@stage $label, $temp, [$($stage)+]);
+		$crate::pipe_try! { @split $label, $temp, [] $($tail)+ }
+	};
+	(@split $label:lifetime, $temp:ident, [$($stage:tt)*] $a:tt => $($tail:tt)+) => {
+		$crate::pipe_try!(@stage $label, $temp, [$($stage)* $a]);
+		$crate::pipe_try! { @split $label, $temp, [] $($tail)+ }
+	};
+	(@split $label:lifetime, $temp:ident, [$($stage:tt)*] $a:tt $b:tt => $($tail:tt)+) => {
+		$crate::pipe_try!(@stage $label, $temp, [$($stage)* $a $b]);
+		$crate::pipe_try! { @split $label, $temp, [] $($tail)+ }
+	};
+	(@split $label:lifetime, $temp:ident, [$($stage:tt)*] $a:tt $b:tt $c:tt => $($tail:tt)+) => {
+		$crate::pipe_try!(@stage $label, $temp, [$($stage)* $a $b $c]);
+		$crate::pipe_try! { @split $label, $temp, [] $($tail)+ }
+	};
+	(@split $label:lifetime, $temp:ident, [$($stage:tt)*] $a:tt $b:tt $c:tt $d:tt $($tail:tt)+) => {
+		$crate::pipe_try!(@split $label, $temp, [$($stage)* $a $b $c $d] $($tail)+)
+	};
+	(@split $label:lifetime, $temp:ident, [$($stage:tt)*] $($rest:tt)*) => {
+		$crate::pipe_try!(@stage $label, $temp, [$($stage)* $($rest)*]);
+		::core::result::Result::Ok($temp)
+	};
+	// A function disabled by `#[cfg]` leaves the previous output in place, without unwrapping it again
+	(@stage $label:lifetime, $temp:ident, [#[cfg($($pred:tt)*)] $($stage:tt)+]) => {
+		#[cfg($($pred)*)]
+		let $temp = $crate::pipe_try!(@unwrap $label, $crate::pipe!($temp => $($stage)+));
+	};
+	// Borrowing never fails, so there is nothing to unwrap
+	(@stage $label:lifetime, $temp:ident, [borrow_rest $(())?]) => {
+		let $temp = &$temp;
+	};
+	(@stage $label:lifetime, $temp:ident, [$($stage:tt)+]) => {
+		let $temp = $crate::pipe_try!(@unwrap $label, $crate::pipe!($temp => $($stage)+));
+	};
+	(@unwrap $label:lifetime, $out:expr) => {
+		match $out {
+			::core::result::Result::Ok(pipe_temp) => pipe_temp,
+			::core::result::Result::Err(pipe_err) => break $label ::core::result::Result::Err(pipe_err),
+		}
+	};
+	($in:expr => $($tail:tt)+) => {
+		match $in {
+			// Eval once and cache
+			pipe_temp => 'pipe_try: {
+				$crate::pipe_try! { @split 'pipe_try, pipe_temp, [] $($tail)+ }
+			}
+		}
+	};
+}
+
+/// A variant of [`pipe!`] which pipes each element of an array through the same functions, producing a new array of the
+/// same length.
+///
+/// Syntax: `pipe_array!(array => fn1 => fn2 => ...)`
+///
+/// Each function accepts the same forms as in [`pipe!`]. This uses [`array::map`], so no allocation takes place.
+#[macro_export]
+macro_rules! pipe_array {
+	($in:expr => $($tail:tt)+) => {
+		$crate::__private::array_map($in, |pipe_elem| $crate::pipe!(pipe_elem => $($tail)+))
+	};
+}
+
+/// Creates a closure which pipes its argument through the given functions, as in [`pipe!`].
+///
+/// Syntax: `compose!(=> fn1 => fn2 => ...)`, which behaves like `move |x| pipe!(x => fn1 => fn2 => ...)`.
+///
+/// Fixed arguments of named functions, paths and keyword stages (such as `y` in `fn1(_, y)`, `u32::max(_, y)` or
+/// `scale(y)`) are evaluated once, when the closure is created, rather than on every call. Arguments which are blanks,
+/// single names, borrows, array literals, macro invocations or closures are left in place, as is everything from the
+/// first named binding or `let` onwards, since later arguments may refer to the names it binds. The closure owns the
+/// evaluated arguments, so it can only be called more than once if they are [`Copy`].
+#[macro_export]
+macro_rules! compose {
+	// Takes the next stage off the front. Calls of named functions are matched as a whole, and any other stage is copied
+	// up to the next `=>`. The state is `[hoisted lets] [stages]`.
+	(@split $lets:tt [$($stages:tt)*] ($name:ident = $($inner:tt)+) $($rest:tt)*) => {
+		// Stages which bind names end hoisting, so the rest are copied as-is
+		$crate::compose!(@done $lets [$($stages)* => ($name = $($inner)+) $($rest)*])
+	};
+	(@split $lets:tt [$($stages:tt)*] let $($rest:tt)+) => {
+		$crate::compose!(@done $lets [$($stages)* => let $($rest)+])
+	};
+	(@split $lets:tt [$($stages:tt)*] $($i:ident).+ $(::<$($gen:ty),+>)? => $($tail:tt)+) => {
+		$crate::compose!(@split $lets [$($stages)* => $($i).+ $(::<$($gen),+>)?] $($tail)+)
+	};
+	(@split $lets:tt $stages:tt $($i:ident).+ $(::<$($gen:ty),+>)? ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		$crate::compose!(@args $lets $stages [$($i).+ $(::<$($gen),+>)?] [] [$(=> $($tail)+)?] $($args)*)
+	};
+	(@split $lets:tt $stages:tt $($seg:ident)::+ $(::<$($gen:ty),+>)? ($($args:tt)*) $(=> $($tail:tt)+)?) => {
+		$crate::compose!(@args $lets $stages [$($seg)::+ $(::<$($gen),+>)?] [] [$(=> $($tail)+)?] $($args)*)
+	};
+	(@split $lets:tt $stages:tt $($rest:tt)+) => {
+		$crate::compose!(@copy $lets $stages [] $($rest)+)
+	};
+	// Copies a stage up to the next `=>`, taking up to four tokens at a time
+	(@copy $lets:tt [$($stages:tt)*] [$($stage:tt)*] => $($tail:tt)+) => {
+		$crate::compose!(@split $lets [$($stages)* => $($stage)*] $($tail)+)
+	};
+	(@copy $lets:tt [$($stages:tt)*] [$($stage:tt)*] $a:tt => $($tail:tt)+) => {
+		$crate::compose!(@split $lets [$($stages)* => $($stage)* $a] $($tail)+)
+	};
+	(@copy $lets:tt [$($stages:tt)*] [$($stage:tt)*] $a:tt $b:tt => $($tail:tt)+) => {
+		$crate::compose!(@split $lets [$($stages)* => $($stage)* $a $b] $($tail)+)
+	};
+	(@copy $lets:tt [$($stages:tt)*] [$($stage:tt)*] $a:tt $b:tt $c:tt => $($tail:tt)+) => {
+		$crate::compose!(@split $lets [$($stages)* => $($stage)* $a $b $c] $($tail)+)
+	};
+	(@copy $lets:tt $stages:tt [$($stage:tt)*] $a:tt $b:tt $c:tt $d:tt $($tail:tt)*) => {
+		$crate::compose!(@copy $lets $stages [$($stage)* $a $b $c $d] $($tail)*)
+	};
+	(@copy $lets:tt [$($stages:tt)*] [$($stage:tt)*] $($rest:tt)*) => {
+		$crate::compose!(@done $lets [$($stages)* => $($stage)* $($rest)*])
+	};
+	(@done [$($lets:tt)*] [$($stages:tt)*]) => {
 		{
-			let pipe_temp = $in; // Eval once and cache
-			pipe!($($i).+($($($arg_head),*,)? $(pipe_temp $(, $arg_tail)*),*) $(=> $($tail)+)?)
+			$($lets)*
+			move |pipe_input| $crate::pipe!(pipe_input $($stages)*)
+		}
+	};
+	// Either leaves each argument of a call in place or evaluates it once into a `let`. The state is
+	// `[hoisted lets] [stages] [callee] [finished arguments] [remaining stages] remaining arguments`.
+	(@args $lets:tt [$($stages:tt)*] [$($callee:tt)*] [$([$($done:tt)*])*] [=> $($tail:tt)+]) => {
+		$crate::compose!(@split $lets [$($stages)* => $($callee)*($($($done)*),*)] $($tail)+)
+	};
+	(@args $lets:tt [$($stages:tt)*] [$($callee:tt)*] [$([$($done:tt)*])*] []) => {
+		$crate::compose!(@done $lets [$($stages)* => $($callee)*($($($done)*),*)])
+	};
+	// A blank or evaluated last argument finishes the call in the same step
+	(@args $lets:tt [$($stages:tt)*] [$($callee:tt)*] [$([$($done:tt)*])*] [=> $($tail:tt)+] _) => {
+		$crate::compose!(@split $lets [$($stages)* => $($callee)*($($($done)*,)* _)] $($tail)+)
+	};
+	(@args $lets:tt $stages:tt $callee:tt [$($done:tt)*] $rest:tt _ $(, $($args:tt)*)?) => {
+		$crate::compose!(@args $lets $stages $callee [$($done)* [_]] $rest $($($args)*)?)
+	};
+	(@args $lets:tt $stages:tt $callee:tt [$($done:tt)*] $rest:tt $name:ident $(, $($args:tt)*)?) => {
+		$crate::compose!(@args $lets $stages $callee [$($done)* [$name]] $rest $($($args)*)?)
+	};
+	(@args $lets:tt $stages:tt $callee:tt [$($done:tt)*] $rest:tt [$($inner:tt)*] $(, $($args:tt)*)?) => {
+		$crate::compose!(@args $lets $stages $callee [$($done)* [[$($inner)*]]] $rest $($($args)*)?)
+	};
+	(@args $lets:tt $stages:tt $callee:tt [$($done:tt)*] $rest:tt $($m:ident)::+ ! $inner:tt $(, $($args:tt)*)?) => {
+		$crate::compose!(@args $lets $stages $callee [$($done)* [$($m)::+ ! $inner]] $rest $($($args)*)?)
+	};
+	(@args $lets:tt $stages:tt $callee:tt $done:tt $rest:tt & $($args:tt)*) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [&] $($args)*)
+	};
+	(@args $lets:tt $stages:tt $callee:tt $done:tt $rest:tt && $($args:tt)*) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [&&] $($args)*)
+	};
+	(@args $lets:tt $stages:tt $callee:tt $done:tt $rest:tt | $($args:tt)*) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [|] $($args)*)
+	};
+	(@args $lets:tt $stages:tt $callee:tt $done:tt $rest:tt || $($args:tt)*) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [||] $($args)*)
+	};
+	(@args $lets:tt $stages:tt $callee:tt $done:tt $rest:tt move $($args:tt)*) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [move] $($args)*)
+	};
+	(@args [$($lets:tt)*] [$($stages:tt)*] [$($callee:tt)*] [$([$($done:tt)*])*] [=> $($tail:tt)+] $e:expr_2021) => {
+		$crate::compose!(
+			@split [$($lets)* let pipe_arg = $e;] [$($stages)* => $($callee)*($($($done)*,)* pipe_arg)] $($tail)+
+		)
+	};
+	(@args [$($lets:tt)*] $stages:tt $callee:tt [$($done:tt)*] $rest:tt $e:expr_2021 $(, $($args:tt)*)?) => {
+		$crate::compose!(@args [$($lets)* let pipe_arg = $e;] $stages $callee [$($done)* [pipe_arg]] $rest $($($args)*)?)
+	};
+	(@args $lets:tt $stages:tt $callee:tt $done:tt $rest:tt $($args:tt)+) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [] $($args)+)
+	};
+	// Leaves an argument in place, collecting its tokens up to the next top-level comma
+	(@keep $lets:tt $stages:tt $callee:tt [$($done:tt)*] $rest:tt [$($arg:tt)*] , $($args:tt)*) => {
+		$crate::compose!(@args $lets $stages $callee [$($done)* [$($arg)*]] $rest $($args)*)
+	};
+	(@keep $lets:tt $stages:tt $callee:tt $done:tt $rest:tt [$($arg:tt)*] $next:tt $($args:tt)*) => {
+		$crate::compose!(@keep $lets $stages $callee $done $rest [$($arg)* $next] $($args)*)
+	};
+	(@keep $lets:tt $stages:tt $callee:tt [$($done:tt)*] $rest:tt [$($arg:tt)*]) => {
+		$crate::compose!(@args $lets $stages $callee [$($done)* [$($arg)*]] $rest)
+	};
+	(=> $($stages:tt)+) => {
+		$crate::compose!(@split [] [] $($stages)+)
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	/// Tests the simple use case - piping to a function which only accepts a single argument.
+	#[test]
+	fn test_simple() {
+		fn test(x: u16) -> f32 {
+			f32::from(x + 1)
+		}
+
+		fn test2(x: f32) -> f32 {
+			x + 1.
+		}
+
+		let x = 3;
+
+		assert_eq!(pipe!(x+1 => test => test2), test2(test(x + 1)));
+		assert_eq!(pipe!(x+1 => test() => test2()), test2(test(x + 1)));
+	}
+
+	/// Tests piping one argument into a function which accepts multiple arguments.
+	#[test]
+	fn test_underscore_fill() {
+		fn test(x: u16) -> u16 {
+			x
+		}
+		fn test2(x: u16, y: u16) -> (u16, u16) {
+			(x, y)
+		}
+
+		let x = 3;
+		let y = 4;
+
+		assert_eq!(pipe!(x-1 => test => test2(_, y)), test2(test(x - 1), y));
+		assert_eq!(pipe!(x-1 => test => test2(y, _)), test2(y, test(x - 1),));
+		assert_eq!(
+			pipe!(x-1 => test => test2(_, _)),
+			test2(test(x - 1), test(x - 1))
+		);
+	}
+
+	/// Make sure blanks are filled inside array literals and macro invocations within an argument.
+	#[test]
+	fn test_nested_blanks() {
+		extern crate std;
+		use std::{vec, vec::Vec};
+
+		fn test(x: u16) -> u16 {
+			x + 1
+		}
+		fn sum(xs: &[u16]) -> u16 {
+			xs.iter().sum()
+		}
+		fn sum_owned(xs: Vec<u16>) -> u16 {
+			xs.into_iter().sum()
+		}
+		fn deref(x: &u16) -> u16 {
+			*x
+		}
+
+		let x = 3;
+		let y = 10;
+
+		assert_eq!(pipe!(x => test => sum(&[_]) => test), test(sum(&[test(x)])));
+		assert_eq!(pipe!(x => test => sum(&[_, y])), sum(&[test(x), y]));
+		assert_eq!(
+			pipe!(x => test => sum(&[y, _, _])),
+			sum(&[y, test(x), test(x)])
+		);
+		assert_eq!(pipe!(x => test => sum_owned(vec![_])), test(x));
+		assert_eq!(pipe!(x => test => sum_owned(vec![_; 3])), 3 * test(x));
+		assert_eq!(pipe!(x => test => deref(&_)), test(x));
+	}
+
+	/// Make sure associated functions are callable.
+	#[test]
+	fn test_associated_functions() {
+		fn test(x: u16) -> u16 {
+			x + 1
+		}
+
+		let x = 3;
+
+		assert_eq!(
+			pipe!(x-2 => test => u16::to_be => u16::isqrt),
+			u16::isqrt(u16::to_be(test(x - 2)))
+		);
+	}
+
+	/// Make sure we can pipe into methods.
+	#[test]
+	fn test_methods() {
+		fn test(x: u16) -> u16 {
+			x + 1
+		}
+
+		let x = 3;
+		let y = 4;
+		assert_eq!(
+			pipe!(x+2 => test => y.max => test),
+			test(y.max(test(x + 2)))
+		);
+
+		let mut y = None;
+		assert_eq!(
+			pipe!(x+2 => y.map_or(_, |x| x + 2) => test),
+			test(y.map_or(x + 2, |x| x + 2))
+		);
+
+		y = Some(x + 2);
+		assert_eq!(
+			pipe!(x+2 => y.map_or(_, |x| x + 2) => test),
+			test(y.map_or(x + 2, |x| x + 2))
+		);
+	}
+
+	/// Make sure we can pipe into a lambda.
+	#[test]
+	#[allow(clippy::redundant_closure_call)]
+	fn test_lambdas() {
+		fn test(x: u16) -> u16 {
+			x + 1
+		}
+
+		let x = 3;
+
+		assert_eq!(
+			pipe!(x => test => |x| {x - 2} => test),
+			test((|x| { x - 2 })(test(x)))
+		);
+	}
+
+	/// Make sure multi-statement block-bodied closures end at the next `=>`.
+	#[test]
+	fn test_block_closures() {
+		fn finish(x: u16) -> u16 {
+			x * 10
+		}
+
+		let x = 3;
+
+		assert_eq!(
+			pipe!(x => |v| {
+				let t = v * 2;
+				let u = if t > 5 { t - 5 } else { t };
+				u + 1
+			} => finish),
+			20
+		);
+		assert_eq!(
+			pipe!(x => move |v: u16| -> u16 {
+				let t = v.pow(2);
+				t + x
+			} => |v| {
+				let halved = v / 2;
+				halved + halved % 2
+			} => finish),
+			60
+		);
+	}
+
+	/// Make sure the initial value can be a block expression with its own control flow.
+	#[test]
+	fn test_block_input() {
+		fn test(x: u16) -> u16 {
+			x + 1
+		}
+
+		let x = 3;
+		let cond = x > 2;
+
+		assert_eq!(
+			pipe!({
+				let y = x + 1;
+				if cond { y } else { y * 2 }
+			} => test),
+			test(x + 1)
+		);
+		assert_eq!(
+			pipe!({
+				let y = x * 2;
+				match y {
+					0 => 10,
+					y if y > 5 => y - 5,
+					_ => y,
+				}
+			} => test => test),
+			test(test(1))
+		);
+	}
+
+	/// Make sure turbofish works on names and on methods called on a blank receiver.
+	#[test]
+	fn test_turbofish() {
+		extern crate std;
+		use std::vec::Vec;
+
+		struct Converter;
+		impl Converter {
+			fn convert<T: From<u16>>(&self, x: u16) -> T {
+				T::from(x)
+			}
+			fn scale<T: From<u16> + core::ops::Mul<Output = T>>(&self, x: u16, y: u16) -> T {
+				T::from(x) * T::from(y)
+			}
+		}
+
+		let conv = Converter;
+		let x = 3;
+
+		assert_eq!(pipe!(" 42 " => str::trim => _.parse::<i64>()), Ok(42i64));
+		assert_eq!(
+			pipe!(0..=x => _.collect::<Vec<_>>()),
+			(0..=x).collect::<Vec<u16>>()
+		);
+		assert_eq!(
+			pipe!(0..=x => _.map(i64::from) => _.collect::<Vec<i64>>()),
+			[0i64, 1, 2, 3]
+		);
+		assert_eq!(pipe!(x => conv.convert::<u32>), 3u32);
+		assert_eq!(pipe!(x => conv.convert::<u64>()), 3u64);
+		assert_eq!(pipe!(x => conv.scale::<u32>(_, 2)), 6u32);
+	}
+
+	/// Make sure a free generic function with an explicit type parameter works as a single-argument stage.
+	#[test]
+	fn test_turbofish_free_fn() {
+		fn convert<T: From<u32>>(x: u32) -> T {
+			T::from(x)
+		}
+		fn finish(x: u64) -> u64 {
+			x << 32
+		}
+
+		assert_eq!(pipe!(1 => convert::<u64> => finish), 1u64 << 32);
+		assert_eq!(pipe!(7 => convert::<f64>() => f64::sqrt), 7f64.sqrt());
+		assert_eq!(pipe!(7 => convert::<u128> => u128::count_ones), 3);
+	}
+
+	/// Make sure positional blanks insert the fields of a tuple output.
+	#[test]
+	fn test_positional_blanks() {
+		fn split3(x: u32) -> (u32, u32, u32) {
+			(x / 100, x / 10 % 10, x % 10)
+		}
+		fn combine(a: u32, b: u32, c: u32) -> u32 {
+			a * 100 + b * 10 + c
+		}
+		fn sum(x: &[u32; 2]) -> u32 {
+			x[0] + x[1]
+		}
+
+		assert_eq!(pipe!(123 => split3 => combine(_2, _0, _1)), 312);
+		assert_eq!(
+			pipe!(123 => split3 => combine(_2, _1, _0) => split3 => combine(_0, 0, _2)),
+			301
+		);
+		assert_eq!(pipe!(123 => split3 => sum(&[_1, _2])), 5);
+		assert_eq!(pipe!((1, 2) => combine(_0, _1, _0)), 121);
+	}
+
+	mod util {
+		pub mod nested {
+			pub fn transform(x: u32) -> u32 {
+				x * 3
+			}
+			pub fn combine(x: u32, y: u32) -> u32 {
+				x * 10 + y
+			}
+			pub fn convert<T: From<u32>>(x: u32) -> T {
+				T::from(x)
+			}
+		}
+	}
+
+	/// Make sure `::`-separated paths work as stages, including with blanks and turbofish.
+	#[test]
+	fn test_paths() {
+		fn finish(x: u32) -> u32 {
+			x + 1
+		}
+
+		let y = 2;
+
+		assert_eq!(pipe!(1 => self::util::nested::transform => finish), 4);
+		assert_eq!(
+			pipe!(1 => crate::tests::util::nested::transform() => finish()),
+			4
+		);
+		assert_eq!(
+			pipe!(1 => util::nested::combine(_, y) => util::nested::combine(y, _)),
+			32
+		);
+		assert_eq!(pipe!(1 => u32::max(_, y) => u32::pow(_, 3)), 8);
+		assert_eq!(pipe!(1 => util::nested::convert::<u64>), 1u64);
+		assert_eq!(
+			pipe!(1 => util::nested::convert::<u64>() => ::core::convert::identity),
+			1u64
+		);
+		assert_eq!(pipe!(" 1 " => str::trim => core::primitive::str::len), 1);
+	}
+
+	/// Make sure blanks can be passed to macros, including as named arguments of formatting macros.
+	#[test]
+	fn test_format_macros() {
+		extern crate std;
+		use core::fmt::Write;
+		use std::{format, string::String, vec};
+
+		fn prep(x: u32) -> u32 {
+			x * 2
+		}
+
+		let mut buf = String::new();
+
+		assert_eq!(
+			pipe!(21 => prep => format!("value is {v}", v = _)),
+			"value is 42"
+		);
+		assert_eq!(
+			pipe!(21 => prep => format!("{} and {w:>3}", _, w = _)),
+			"42 and  42"
+		);
+		assert_eq!(pipe!(1 => vec![_, 2] => _.len()), 2);
+		assert!(pipe!(21 => prep => write!(buf, "[{v}]", v = _)).is_ok());
+		assert_eq!(buf, "[42]");
+	}
+
+	/// Make sure collection constructors such as `HashMap::from_iter` work as stages.
+	#[test]
+	fn test_from_iter() {
+		extern crate std;
+		use std::collections::{BTreeMap, HashMap, HashSet};
+
+		fn prep(x: &str) -> impl Iterator<Item = (&str, usize)> {
+			x.split_whitespace().map(|w| (w, w.len()))
+		}
+		fn report<K, V>(map: HashMap<K, V>) -> usize {
+			map.len()
+		}
+
+		let words = "a bb ccc bb";
+
+		assert_eq!(pipe!(words => prep => HashMap::from_iter => report), 3);
+		assert_eq!(
+			pipe!(words => prep => BTreeMap::from_iter => _.into_iter().next()),
+			Some(("a", 1))
+		);
+		assert_eq!(
+			pipe!(words => str::split_whitespace => HashSet::<&str>::from_iter => _.len()),
+			3
+		);
+		assert_eq!(
+			pipe!(words => prep => <HashMap<_, _> as FromIterator<_>>::from_iter => _.get("ccc").copied()),
+			Some(3)
+		);
+	}
+
+	/// Make sure stages with const generic arguments are called like functions.
+	#[test]
+	fn test_const_generics() {
+		fn repeat<const N: usize>(x: u8) -> [u8; N] {
+			[x; N]
+		}
+		fn pad<const N: usize>(x: u8, fill: u8) -> [u8; 3] {
+			let mut out = [fill; 3];
+			out[..3 - N].fill(x);
+			out
+		}
+
+		const M: usize = 2;
+
+		assert_eq!(pipe!(1 => repeat::<{ M + 1 }>), [1, 1, 1]);
+		assert_eq!(pipe!(1 => repeat::<{ M }>() => _.len()), 2);
+		assert_eq!(pipe!(1 => pad::<{ M }>(_, 7)), [1, 7, 7]);
+	}
+
+	/// Make sure empty parentheses are accepted uniformly by every kind of single-argument stage.
+	#[test]
+	fn test_empty_parens() {
+		struct Point {
+			x: u32,
+		}
+		struct Doubler;
+		impl Doubler {
+			fn apply(&self, x: u32) -> u32 {
+				x * 2
+			}
+			fn new_point(x: u32) -> Point {
+				Point { x }
+			}
+		}
+		fn inc(x: u32) -> u32 {
+			x + 1
+		}
+		fn convert<T: From<u32>>(x: u32) -> T {
+			T::from(x)
+		}
+
+		let d = Doubler;
+		let f = |x: u32| x * 3;
+
+		// Free functions
+		assert_eq!(pipe!(1 => inc => inc()), 3);
+		// Associated functions
+		assert_eq!(pipe!(7 => u32::count_ones => u32::count_ones()), 2);
+		assert_eq!(pipe!(4 => Doubler::new_point() => _.x), 4);
+		// Methods on a receiver
+		assert_eq!(pipe!(1 => d.apply => d.apply()), 4);
+		// Turbofish
+		assert_eq!(pipe!(1 => convert::<u64> => u64::count_ones()), 1);
+		assert_eq!(pipe!(1 => convert::<u64>()), 1u64);
+		// Parenthesized expressions
+		assert_eq!(pipe!(1 => (f) => (f)()), 9);
+		// Blank receivers are applied as written
+		assert_eq!(pipe!(1 => inc => _.count_ones()), 1);
+	}
+
+	/// Make sure const-generic array helpers work as single-argument stages.
+	#[test]
+	fn test_const_generic_arrays() {
+		fn compute(x: u8) -> u8 {
+			x * 2
+		}
+		fn replicate<const N: usize, T: Copy>(v: T) -> [T; N] {
+			[v; N]
+		}
+		fn fill<const N: usize>(v: u8) -> [u8; N] {
+			[v; N]
+		}
+
+		assert_eq!(pipe!(3 => compute => replicate::<4, _>), [6u8; 4]);
+		assert_eq!(pipe!(3 => compute => replicate::<4, u8>()), [6, 6, 6, 6]);
+		assert_eq!(pipe!(3 => fill::<4>), [3u8; 4]);
+		assert_eq!(
+			pipe!(3 => compute => replicate::<2, _> => replicate::<3, _>),
+			[[6u8; 2]; 3]
+		);
+	}
+
+	/// Make sure `<_ as Trait>::method` calls the trait method with the output as the receiver.
+	#[test]
+	fn test_qualified_receiver() {
+		trait Measure {
+			fn len(&self) -> usize;
+			fn scaled(&self, factor: usize) -> usize;
+		}
+		struct Rope(&'static str);
+		impl Rope {
+			fn len(&self) -> usize {
+				self.0.len()
+			}
+		}
+		impl Measure for Rope {
+			fn len(&self) -> usize {
+				self.0.chars().count()
+			}
+			fn scaled(&self, factor: usize) -> usize {
+				Measure::len(self) * factor
+			}
+		}
+
+		fn build(x: &'static str) -> Rope {
+			Rope(x)
+		}
+		fn done(x: usize) -> usize {
+			x + 1
+		}
+
+		assert_eq!(pipe!("héllo" => build => borrow_rest => _.len() => done), 7);
+		assert_eq!(
+			pipe!("héllo" => build => borrow_rest => <_ as Measure>::len() => done),
+			6
+		);
+		assert_eq!(
+			pipe!("héllo" => build => borrow_rest => <_ as Measure>::len),
+			5
+		);
+		assert_eq!(
+			pipe!("héllo" => build => borrow_rest => <_ as Measure>::scaled(2)),
+			10
+		);
+		assert_eq!(pipe!(2u32 => <_ as core::ops::Add<u32>>::add(_)), 4);
+	}
+
+	/// Make sure parenthesized groups of functions behave like the same functions without parentheses.
+	#[test]
+	fn test_groups() {
+		fn a(x: u32) -> u32 {
+			x + 1
+		}
+		fn b(x: u32) -> u32 {
+			x * 2
+		}
+		fn c(x: u32, y: u32) -> u32 {
+			x * 10 + y
+		}
+
+		let g = |x: u32| x - 1;
+
+		assert_eq!(
+			pipe!(3 => (a => b => c(_, 4)) => a),
+			pipe!(3 => a => b => c(_, 4) => a)
+		);
+		assert_eq!(
+			pipe!(3 => a => (b => (a => b)) => g),
+			pipe!(3 => a => b => a => b => g)
+		);
+		assert_eq!(pipe!(3 => (scale(3) => offset(1)) => (g)), 9);
+		assert_eq!(pipe!(3 => ((n = a) => c(_, n)) => b), 88);
+		assert_eq!(pipe!(3 => ((n = a) => b) => c(_, n)), 84);
+		assert_eq!(pipe!(0 => (skip_if_default(5) => a) => a), 5);
+	}
+
+	/// Make sure named intermediates can be borrowed by later stages.
+	#[test]
+	fn test_named_intermediate() {
+		struct Config {
+			offset: u16,
+		}
+		impl Config {
+			fn threshold(&self) -> u16 {
+				self.offset * 2
+			}
+		}
+
+		fn load_config(offset: u16) -> Config {
+			Config { offset }
+		}
+		fn process(x: u16, cfg: &Config) -> u16 {
+			x + cfg.offset
+		}
+
+		let x = 3;
+
+		assert_eq!(
+			pipe!(x => (cfg = load_config) => _.threshold() => process(_, &cfg) => process(_, &cfg)),
+			12
+		);
+		assert_eq!(pipe!(x => (y = _.pow(2)) => _.min(4) => y.max(_)), 9);
+	}
+
+	/// Make sure `#[cfg]`-gated stages are only applied when the predicate holds.
+	#[test]
+	fn test_cfg_stage() {
+		fn test(x: u16) -> u16 {
+			x + 1
+		}
+		fn double(x: u16) -> u16 {
+			x * 2
+		}
+
+		let x = 3;
+
+		assert_eq!(
+			pipe!(x => test => #[cfg(test)] double => test),
+			test(double(test(x)))
+		);
+		assert_eq!(
+			pipe!(x => test => #[cfg(not(test))] double => test),
+			test(test(x))
+		);
+		assert_eq!(pipe!(x => #[cfg(not(test))] _.max(10)), x);
+		assert_eq!(pipe!(x => test => #[cfg(test)] _.pow(2)), 16);
+	}
+
+	/// Make sure a named initial value can be referenced by later stages.
+	#[test]
+	fn test_named_input() {
+		fn compute(x: u16) -> u16 {
+			x * 3
+		}
+		fn combine(x: u16, y: u16) -> (u16, u16) {
+			(x, y)
+		}
+
+		let x = 3;
+
+		assert_eq!(
+			pipe!((input = x + 1) => compute => compute => combine(_, input)),
+			(36, 4)
+		);
+		assert_eq!(
+			pipe!((input = x) => compute => _.min(input) => combine(input, _)),
+			(3, 3)
+		);
+	}
+
+	/// Make sure all names bound by a destructuring stage are usable afterwards.
+	#[test]
+	fn test_destructure() {
+		fn parse_prefix(x: &str) -> (u16, &str) {
+			let (parsed, rest) = x.split_at(1);
+			(parsed.parse().unwrap(), rest)
+		}
+		fn validate(parsed: u16, rest: &str) -> u16 {
+			parsed + rest.len() as u16
+		}
+		fn combine(x: u16, y: u16, rest: &str) -> (u16, u16, &str) {
+			(x, y, rest)
+		}
+
+		assert_eq!(
+			pipe!("3abc" => parse_prefix => let (parsed, rest) => validate(parsed, rest) => combine(_, parsed, rest)),
+			(6, 3, "abc")
+		);
+		assert_eq!(
+			pipe!([1u16, 2] => let [a, b] => a + b => combine(_, b, "") => let (sum, ..) => sum * a),
+			3
+		);
+	}
+
+	/// Make sure methods with several fixed arguments can be called on a blank receiver.
+	#[test]
+	fn test_receiver_fixed_args() {
+		fn lookup(x: u16) -> Option<u16> {
+			x.checked_sub(2)
+		}
+		fn default() -> u16 {
+			100
+		}
+		fn double(x: u16) -> u16 {
+			x * 2
+		}
+
+		assert_eq!(pipe!(5 => lookup => _.map_or_else(default, double)), 6);
+		assert_eq!(pipe!(1 => lookup => _.map_or_else(default, double)), 100);
+		assert_eq!(
+			pipe!(1 => lookup => _.map_or_else(|| 7, |x| x + 1) => double),
+			14
+		);
+	}
+
+	/// Make sure async methods can be called and awaited on a blank receiver.
+	#[test]
+	fn test_receiver_await() {
+		use core::{
+			future::Future,
+			pin::pin,
+			task::{Context, Poll, Waker},
+		};
+
+		fn block_on<F: Future>(fut: F) -> F::Output {
+			let mut fut = pin!(fut);
+			let mut cx = Context::from_waker(Waker::noop());
+			loop {
+				if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+					return out;
+				}
+			}
+		}
+
+		struct Client {
+			id: u16,
+		}
+		impl Client {
+			async fn send(self, msg: u16) -> u16 {
+				self.id + msg
+			}
+		}
+
+		fn build(id: u16) -> Client {
+			Client { id }
+		}
+		fn handle(x: u16) -> u16 {
+			x * 2
+		}
+
+		let msg = 4;
+
+		assert_eq!(
+			block_on(async { pipe!(3 => build => _.send(msg).await => handle) }),
+			14
+		);
+		assert_eq!(
+			block_on(async { pipe!(3 => build => _.send(msg).await) }),
+			7
+		);
+	}
+
+	/// Tests the `reduce` keyword stage.
+	#[test]
+	fn test_reduce() {
+		assert_eq!(
+			pipe!(0..10u32 => reduce(|a, b| a.max(b)) => Option::unwrap_or_default),
+			9
+		);
+		assert_eq!(pipe!(0..0u32 => reduce(|a, b| a.max(b))), None);
+	}
+
+	/// Make sure `pipe_try!` unwraps each stage and stops at the first error.
+	#[test]
+	fn test_pipe_try() {
+		fn half(x: u16) -> Result<u16, &'static str> {
+			if x.is_multiple_of(2) {
+				Ok(x / 2)
+			} else {
+				Err("odd")
+			}
+		}
+		fn sub(x: u16, y: u16) -> Result<u16, &'static str> {
+			x.checked_sub(y).ok_or("underflow")
+		}
+
+		assert_eq!(pipe_try!(12 => half => half), Ok(3));
+		assert_eq!(pipe_try!(12 => half => sub(_, 2) => half), Ok(2));
+		assert_eq!(pipe_try!(12 => half => half => half), Err("odd"));
+		assert_eq!(pipe_try!(12 => half => sub(_, 7) => half), Err("underflow"));
+
+		// Later stages are skipped once an error is hit
+		let mut calls = 0;
+		let mut counted = |x: u16| {
+			calls += 1;
+			half(x)
+		};
+		assert_eq!(pipe_try!(3 => half => counted), Err("odd"));
+		assert_eq!(calls, 0);
+
+		// Disabled functions leave the previous output as-is
+		assert_eq!(pipe_try!(12 => half => #[cfg(any())] half => half), Ok(3));
+		assert_eq!(pipe_try!(12 => #[cfg(all())] half => half), Ok(3));
+
+		fn check(x: &u16) -> Result<u16, &'static str> {
+			if *x > 0 { Ok(*x) } else { Err("zero") }
+		}
+		assert_eq!(pipe_try!(12 => half => borrow_rest => check), Ok(6));
+		assert_eq!(pipe_try!(0 => half => borrow_rest => check), Err("zero"));
+	}
+
+	/// Tests the `ok` and `err` keyword stages.
+	#[test]
+	fn test_ok_err() {
+		fn parse(x: &str) -> Result<u16, &str> {
+			x.parse().map_err(|_| x)
+		}
+
+		assert_eq!(pipe!("3" => parse => ok), Some(3));
+		assert_eq!(pipe!("a" => parse => ok => Option::unwrap_or_default), 0);
+		assert_eq!(pipe!("3" => parse => err()), None);
+		assert_eq!(pipe!("a" => parse => err), Some("a"));
+	}
+
+	/// Tests the `chunks` and `windows` keyword stages.
+	#[test]
+	fn test_chunks_windows() {
+		extern crate std;
+		use std::vec::Vec;
+
+		fn sum(xs: &[u8]) -> u8 {
+			xs.iter().sum()
+		}
+
+		let data = [1u8, 2, 3, 4, 5];
+
+		assert_eq!(
+			pipe!(&data[..] => chunks(2) => _.map(sum) => _.collect::<Vec<_>>()),
+			[3, 7, 5]
+		);
+		assert_eq!(
+			pipe!(data.as_slice() => windows(2) => _.map(sum) => _.collect::<Vec<_>>()),
+			[3, 5, 7, 9]
+		);
+	}
+
+	/// Tests the `clone` and `to_owned` keyword stages.
+	#[test]
+	fn test_clone() {
+		extern crate std;
+		use std::{borrow::ToOwned, string::String};
+
+		fn build(x: &str) -> String {
+			String::from(x) + "!"
+		}
+		fn consume(x: String) -> usize {
+			x.len()
+		}
+
+		let s = build("abc");
+
+		assert_eq!(pipe!(&s => clone => consume), 4);
+		assert_eq!(s, "abc!");
+		assert_eq!(pipe!("abc" => build => clone() => consume), 4);
+		assert_eq!(pipe!("abc" => to_owned => _.len()), 3);
+		assert_eq!(pipe!(s.as_str() => to_owned => consume), 4);
+	}
+
+	/// Tests the `get_or_insert` and `get_or_insert_with` keyword stages.
+	#[test]
+	fn test_get_or_insert() {
+		fn bump(x: &mut u16) -> u16 {
+			*x += 1;
+			*x
+		}
+
+		let mut computed = 0;
+		let mut compute = || {
+			computed += 1;
+			5
+		};
+
+		let mut slot = None;
+		assert_eq!(
+			pipe!(&mut slot => get_or_insert_with(&mut compute) => bump),
+			6
+		);
+		assert_eq!(
+			pipe!(&mut slot => get_or_insert_with(&mut compute) => bump),
+			7
+		);
+		assert_eq!(computed, 1);
+		assert_eq!(slot, Some(7));
+
+		let mut slot = None;
+		assert_eq!(pipe!(&mut slot => get_or_insert(1) => bump), 2);
+		assert_eq!(pipe!(&mut slot => get_or_insert(1) => bump), 3);
+	}
+
+	/// Tests the `clamp`, `min` and `max` keyword stages.
+	#[test]
+	fn test_clamp() {
+		fn compute(x: i32) -> i32 {
+			x * 10
+		}
+
+		assert_eq!(pipe!(-3 => compute => clamp(0, 100)), 0);
+		assert_eq!(pipe!(5 => compute => clamp(0, 100)), 50);
+		assert_eq!(pipe!(20 => compute => clamp(0, 100)), 100);
+		assert_eq!(pipe!(20 => compute => min(42)), 42);
+		assert_eq!(pipe!(-3 => compute => max(0) => compute), 0);
+		assert_eq!(pipe!(1.5f32 => clamp(0., 1.)), 1.);
+	}
+
+	/// Tests the `context` and `with_context` keyword stages.
+	#[cfg(feature = "anyhow")]
+	#[test]
+	fn test_context() {
+		extern crate std;
+		use std::format;
+
+		fn parse(x: &str) -> Result<u16, core::num::ParseIntError> {
+			x.parse()
+		}
+
+		assert_eq!(
+			pipe!("3" => parse => context("parsing x") => Result::ok),
+			Some(3)
+		);
+
+		let err = pipe!("a" => parse => context("parsing x") => Result::unwrap_err);
+		assert_eq!(format!("{err}"), "parsing x");
+		assert_eq!(
+			format!("{}", err.root_cause()),
+			"invalid digit found in string"
+		);
+
+		let x = "b";
+		let err =
+			pipe!(x => parse => with_context(|| format!("parsing {x}")) => Result::unwrap_err);
+		assert_eq!(format!("{err}"), "parsing b");
+	}
+
+	/// Make sure `context` and `with_context` are ordinary function stages without the `anyhow` feature.
+	#[cfg(not(feature = "anyhow"))]
+	#[test]
+	fn test_context_without_feature() {
+		fn context(tag: char) -> impl Fn(u32) -> (char, u32) {
+			move |x| (tag, x)
+		}
+		fn with_context(f: impl Fn() -> char) -> impl Fn(u32) -> (char, u32) {
+			move |x| (f(), x)
+		}
+
+		assert_eq!(pipe!(3 => context('a')), ('a', 3));
+		assert_eq!(pipe!(3 => with_context(|| 'b') => _.1), 3);
+	}
+
+	/// Tests the `collect_string` keyword stage.
+	#[cfg(feature = "alloc")]
+	#[test]
+	fn test_collect_string() {
+		extern crate std;
+		use std::string::String;
+
+		fn shout(x: String) -> String {
+			x + "!"
+		}
+
+		assert_eq!(
+			pipe!("abc".chars() => _.map(|c| c.to_ascii_uppercase()) => collect_string => shout),
+			"ABC!"
+		);
+		assert_eq!(pipe!(["a", "b"] => _.into_iter() => collect_string()), "ab");
+	}
+
+	/// Make sure `collect_string` is an ordinary function stage without the `alloc` feature.
+	#[cfg(not(feature = "alloc"))]
+	#[test]
+	fn test_collect_string_without_feature() {
+		fn collect_string(x: impl Iterator<Item = char>) -> usize {
+			x.count()
+		}
+
+		assert_eq!(pipe!("abc".chars() => collect_string), 3);
+		assert_eq!(pipe!("ab".chars() => collect_string()), 2);
+	}
+
+	/// Tests the `find` and `position` keyword stages.
+	#[test]
+	fn test_find_position() {
+		fn square(x: u32) -> u32 {
+			x * x
+		}
+
+		assert_eq!(pipe!(1..10 => _.map(square) => find(|x| *x > 10)), Some(16));
+		assert_eq!(
+			pipe!(1..10 => _.map(square) => position(|x| x > 10)),
+			Some(3)
+		);
+		assert_eq!(pipe!(1..3 => _.map(square) => find(|x| *x > 10)), None);
+		assert_eq!(
+			pipe!(1..3 => _.map(square) => position(|x| x > 10) => Option::unwrap_or_default),
+			0
+		);
+	}
+
+	/// Tests the `split`, `split_whitespace` and `lines` keyword stages.
+	#[test]
+	fn test_split() {
+		extern crate std;
+		use std::vec::Vec;
+
+		let line = " 1,2,30 ";
+
+		assert_eq!(
+			pipe!(line => str::trim => split(',') => _.map(str::parse::<u32>) => _.collect::<Result<Vec<_>, _>>()),
+			Ok(std::vec![1, 2, 30])
+		);
+		assert_eq!(pipe!("a  b\tc" => split_whitespace => _.count()), 3);
+		assert_eq!(
+			pipe!("1\n2\n3\n" => lines() => _.map(str::parse::<u32>) => _.map(Result::unwrap) => _.sum::<u32>()),
+			6
+		);
+	}
+
+	/// Tests the `apply_all` keyword stage.
+	#[test]
+	fn test_apply_all() {
+		use core::cell::Cell;
+
+		fn double(x: u32) -> u32 {
+			x * 2
+		}
+		fn increment(x: u32) -> u32 {
+			x + 1
+		}
+		fn square(x: u32) -> u32 {
+			x * x
+		}
+
+		let stages: [fn(u32) -> u32; 3] = [double, increment, square];
+		let none: &[fn(u32) -> u32] = &[];
+
+		assert_eq!(pipe!(3 => increment => apply_all(&stages) => double), 162);
+		assert_eq!(pipe!(3 => apply_all(stages.iter().rev())), 20);
+		assert_eq!(pipe!(3 => apply_all(none)), 3);
+
+		// The input is evaluated before the functions are
+		let order = Cell::new(0);
+		let input = || {
+			order.set(order.get() * 10 + 1);
+			3
+		};
+		let make_stages = || {
+			order.set(order.get() * 10 + 2);
+			stages
+		};
+		assert_eq!(pipe!(input() => apply_all(make_stages())), 49);
+		assert_eq!(order.get(), 12);
+	}
+
+	/// Tests the `unwrap` and `expect` keyword stages.
+	#[test]
+	fn test_unwrap_expect() {
+		fn parse(x: &str) -> Result<u16, core::num::ParseIntError> {
+			x.parse()
+		}
+		fn double(x: u16) -> u16 {
+			x * 2
+		}
+
+		assert_eq!(pipe!("3" => parse => unwrap => double), 6);
+		assert_eq!(pipe!("3" => parse => expect("bad input") => double), 6);
+		assert_eq!(pipe!("3" => parse => ok => unwrap() => double), 6);
+		assert_eq!(pipe!(Some(4) => expect("missing") => double), 8);
+	}
+
+	/// Make sure the `expect` keyword stage panics with its message on an `Err`.
+	#[test]
+	#[should_panic(expected = "bad input")]
+	fn test_expect_err() {
+		fn parse(x: &str) -> Result<u16, core::num::ParseIntError> {
+			x.parse()
+		}
+
+		pipe!("a" => parse => expect("bad input"));
+	}
+
+	/// Make sure the `unwrap` keyword stage panics on a `None`.
+	#[test]
+	#[should_panic]
+	fn test_unwrap_none() {
+		pipe!(None::<u16> => unwrap);
+	}
+
+	/// Tests the `extract` keyword stage.
+	#[test]
+	fn test_extract() {
+		enum Kind {
+			Value(u16),
+			Empty,
+		}
+
+		fn classify(x: u16) -> Kind {
+			if x > 0 { Kind::Value(x) } else { Kind::Empty }
+		}
+		fn double(x: u16) -> u16 {
+			x * 2
+		}
+
+		assert_eq!(
+			pipe!(3 => classify => extract(Kind::Value(v) => v, else 0) => double),
+			6
+		);
+		assert_eq!(
+			pipe!(0 => classify => extract(Kind::Value(v) => v, else 10) => double),
+			20
+		);
+		assert!(pipe!(0 => classify => extract(Kind::Empty => true, else false)));
+	}
+
+	/// Tests the `push_mut` and `insert_mut` keyword stages.
+	#[test]
+	fn test_push_mut() {
+		extern crate std;
+		use std::{vec, vec::Vec};
+
+		assert_eq!(
+			pipe!(Vec::new() => push_mut(1) => push_mut(2) => push_mut(3)),
+			[1, 2, 3]
+		);
+		assert_eq!(
+			pipe!(vec![1, 3] => insert_mut(1, 2) => insert_mut(0, 0) => _.len()),
+			4
+		);
+	}
+
+	/// Tests the `scale` and `offset` keyword stages.
+	#[test]
+	fn test_scale_offset() {
+		fn read_adc(channel: u8) -> u16 {
+			[0, 2048, 4096][usize::from(channel)]
+		}
+
+		let volts = |channel| pipe!(channel => read_adc => f32::from => scale(3.3 / 4096.0) => offset(-1.65));
+
+		assert!((volts(0) + 1.65).abs() < 1e-6);
+		assert!(volts(1).abs() < 1e-6);
+		assert!((volts(2) - 1.65).abs() < 1e-6);
+		assert_eq!(pipe!(3 => offset(1) => scale(2)), 8);
+	}
+
+	/// Tests the `then` and `then_some` keyword stages.
+	#[test]
+	fn test_then() {
+		fn is_valid(x: u16) -> bool {
+			x < 10
+		}
+
+		let payload = 'p';
+
+		assert_eq!(pipe!(3 => is_valid => then_some(payload)), Some('p'));
+		assert_eq!(pipe!(30 => is_valid => then_some(payload)), None);
+		assert_eq!(pipe!(3 => is_valid => then(|| payload.len_utf8())), Some(1));
+		assert_eq!(
+			pipe!(30 => is_valid => then(|| -> usize { unreachable!() })),
+			None
+		);
+	}
+
+	/// Tests the `count`, `sum` and `product` keyword stages.
+	#[test]
+	fn test_count_sum_product() {
+		fn report(x: u64) -> u64 {
+			x
+		}
+
+		assert_eq!(pipe!(0..10 => _.filter(|x| x % 3 == 0) => count), 4);
+		assert_eq!(pipe!(1..=100u64 => sum => report), 5050);
+		assert_eq!(pipe!((1..=100u32).map(u64::from) => sum::<u64>()), 5050);
+		assert_eq!(pipe!(1..=5 => product::<u32>), 120);
+		assert_eq!(pipe!(1..=5u64 => product() => report), 120);
+	}
+
+	/// Tests the `to_cow` and `into_owned` keyword stages.
+	#[cfg(feature = "alloc")]
+	#[test]
+	fn test_cow() {
+		extern crate std;
+		use std::{borrow::Cow, string::String};
+
+		fn maybe_modify(x: Cow<str>) -> Cow<str> {
+			if x.contains(' ') {
+				Cow::Owned(x.replace(' ', "_"))
+			} else {
+				x
+			}
+		}
+
+		let unchanged = pipe!("abc" => to_cow => maybe_modify);
+		assert!(matches!(unchanged, Cow::Borrowed("abc")));
+
+		let owned: String = pipe!("a b" => to_cow => maybe_modify => into_owned);
+		assert_eq!(owned, "a_b");
+		assert_eq!(
+			pipe!(String::from("abc") => to_cow() => into_owned()),
+			"abc"
+		);
+	}
+
+	/// Make sure `to_cow` is an ordinary function stage without the `alloc` feature.
+	#[cfg(not(feature = "alloc"))]
+	#[test]
+	fn test_to_cow_without_feature() {
+		fn to_cow(x: &str) -> usize {
+			x.len()
+		}
+
+		assert_eq!(pipe!("abc" => to_cow), 3);
+		assert_eq!(pipe!("ab" => to_cow()), 2);
+	}
+
+	/// Tests the `peekable` and `peek_then` keyword stages.
+	#[test]
+	fn test_peek() {
+		fn lex(x: &str) -> core::str::SplitWhitespace<'_> {
+			x.split_whitespace()
+		}
+
+		let mut lookahead = None;
+		let count =
+			pipe!("let x = 1" => lex => peekable => peek_then(|t| lookahead = t.copied()) => count);
+
+		assert_eq!(lookahead, Some("let"));
+		assert_eq!(count, 4);
+
+		let first =
+			pipe!("" => lex => peekable() => peek_then(|t| assert!(t.is_none())) => _.next());
+		assert_eq!(first, None);
+	}
+
+	/// Make sure stages returning borrows of the input chain, even when the input borrows a temporary.
+	#[test]
+	fn test_borrowing_stages() {
+		extern crate std;
+		use std::{string::String, vec::Vec};
+
+		fn split(x: &str, sep: char) -> Option<(&str, &str)> {
+			x.split_once(sep)
+		}
+		fn key(x: &str) -> &str {
+			split(x, '=').map_or(x, |(k, _)| k)
+		}
+
+		let line = String::from("  name = value ");
+		let k: &str = pipe!(line.as_str() => str::trim => key => str::trim_end);
+		assert_eq!(k, "name");
+		assert!(core::ptr::eq(k.as_ptr(), line[2..].as_ptr()));
+
+		assert_eq!(
+			pipe!(String::from(" a=b ").as_str() => str::trim => split(_, '=')),
+			Some(("a", "b"))
+		);
+		assert_eq!(
+			pipe!(String::from(" a=b ").as_str() => (s = str::trim) => key => s.strip_prefix(_)),
+			Some("=b")
+		);
+		assert_eq!(
+			pipe!(String::from("a b").as_str() => split_whitespace => _.collect::<Vec<_>>() => push_mut("c") => insert_mut(0, "z")),
+			["z", "a", "b", "c"]
+		);
+		assert_eq!(
+			pipe!(String::from(" a b").as_str() => split_whitespace => peekable => peek_then(|_| ()) => _.next()),
+			Some("a")
+		);
+	}
+
+	/// Tests the `saturating_add`, `saturating_sub` and `saturating_mul` keyword stages.
+	#[test]
+	fn test_saturating() {
+		fn read(x: u8) -> u8 {
+			x
+		}
+
+		assert_eq!(
+			pipe!(100 => read => saturating_add(10) => saturating_sub(5)),
+			105
+		);
+		assert_eq!(
+			pipe!(250 => read => saturating_add(10) => saturating_sub(5)),
+			250
+		);
+		assert_eq!(
+			pipe!(3 => read => saturating_sub(5) => saturating_add(1)),
+			1
+		);
+		assert_eq!(pipe!(100 => read => saturating_mul(3)), u8::MAX);
+		assert_eq!(pipe!(-100i8 => saturating_mul(2)), i8::MIN);
+		assert_eq!(
+			pipe!(i8::MIN => saturating_sub(1) => saturating_add(-1)),
+			i8::MIN
+		);
+	}
+
+	/// Tests the `record` keyword stage.
+	#[test]
+	fn test_record() {
+		extern crate std;
+		use std::{string::String, vec::Vec};
+
+		fn double(x: u32) -> u32 {
+			x * 2
+		}
+
+		let mut trace = Vec::new();
+		let out = pipe!(3 => record(&mut trace) => double => record(&mut trace) => double);
+		assert_eq!(out, 12);
+		assert_eq!(trace, [3, 6]);
+
+		let mut words = Vec::new();
+		let buf = &mut words;
+		let len =
+			pipe!("a b c" => split_whitespace => _.collect::<Vec<_>>() => record(buf) => _.len());
+		assert_eq!(len, 3);
+		assert_eq!(words, [["a", "b", "c"]]);
+
+		let mut seen = Vec::new();
+		assert_eq!(
+			pipe!(String::from(" a ").as_str() => record(&mut seen) => str::trim),
+			"a"
+		);
+	}
+
+	/// Tests the `and` and `or` keyword stages.
+	#[test]
+	fn test_and_or() {
+		fn parse(x: &str) -> Result<u32, &str> {
+			x.parse().map_err(|_| x)
+		}
+
+		let good: Result<char, &str> = Ok('b');
+		let bad: Result<char, &str> = Err("b");
+
+		assert_eq!(pipe!("1" => parse => and(good)), Ok('b'));
+		assert_eq!(pipe!("1" => parse => and(bad)), Err("b"));
+		assert_eq!(pipe!("a" => parse => and(good)), Err("a"));
+		assert_eq!(pipe!("a" => parse => and(bad)), Err("a"));
+
+		assert_eq!(pipe!("1" => parse => or(parse("2"))), Ok(1));
+		assert_eq!(pipe!("1" => parse => or(parse("b"))), Ok(1));
+		assert_eq!(pipe!("a" => parse => or(parse("2"))), Ok(2));
+		assert_eq!(pipe!("a" => parse => or(parse("b"))), Err("b"));
+		assert_eq!(
+			pipe!("a" => parse => ok => or(Some(5)) => and(Some('c'))),
+			Some('c')
+		);
+	}
+
+	/// Tests the `copied` and `cloned` keyword stages.
+	#[test]
+	fn test_copied_cloned() {
+		extern crate std;
+		use std::{string::String, vec::Vec};
+
+		fn prep(x: &[u32]) -> core::slice::Iter<'_, u32> {
+			x.iter()
+		}
+
+		let data: &[u32] = &[1, 2, 3];
+		let names = [String::from("a"), String::from("b")];
+
+		assert_eq!(pipe!(data => prep => copied => sum::<u32>()), 6);
+		assert_eq!(pipe!(data => prep => copied() => _.max()), Some(3));
+		assert_eq!(
+			pipe!(&names => _.iter() => cloned => _.collect::<Vec<_>>()),
+			names
+		);
+		assert_eq!(
+			pipe!(&names => _.first() => cloned),
+			Some(String::from("a"))
+		);
+	}
+
+	/// Make sure `compose!` builds a reusable closure, evaluating fixed arguments only once.
+	#[test]
+	fn test_compose() {
+		use core::cell::Cell;
+
+		fn finish(x: u32) -> u32 {
+			x + 1
 		}
-	};
-	($in:expr => ($e:expr) ($($($arg_head:expr_2021),*,)? $(_ $(, $arg_tail:expr_2021)*),*) $(=> $($tail:tt)+)?) => {
-		{
-			let pipe_temp = $in; // Eval once and cache
-			pipe!($e($($($arg_head),*,)? $(pipe_temp $(, $arg_tail)*),*) $(=> $($tail)+)?)
+		fn weigh(x: u32, w: u32) -> u32 {
+			x * w
 		}
-	};
-	($in:expr => $e:expr $(=> $($tail:tt)+)?) => {
-		pipe!($e($in) $(=> $($tail)+)?)
-	};
-}
 
-#[cfg(test)]
-mod tests {
-	/// Tests the simple use case - piping to a function which only accepts a single argument.
+		let calls = Cell::new(0);
+		let expensive_factor = || {
+			calls.set(calls.get() + 1);
+			3
+		};
+
+		let f =
+			compose!(=> scale(expensive_factor()) => finish => weigh(_, expensive_factor() - 1));
+		assert_eq!(calls.get(), 2);
+		assert_eq!(f(1), 8);
+		assert_eq!(f(2), 14);
+		assert_eq!(f(5), 32);
+		assert_eq!(calls.get(), 2);
+
+		let limit = 10;
+		let g = compose!(=> finish => _.min(limit) => Some => extract(Some(x) => x * 2, else 0) => clamp(0, limit + 5));
+		assert_eq!(g(3), 8);
+		assert_eq!(g(30), 15);
+
+		let h = compose!(=> (x = finish) => weigh(_, x) => finish);
+		assert_eq!(h(2), 10);
+
+		let floor = Cell::new(0);
+		let expensive_floor = || {
+			floor.set(floor.get() + 1);
+			4
+		};
+		let k = compose!(=> u32::max(_, expensive_floor()) => finish);
+		assert_eq!(k(1), 5);
+		assert_eq!(k(7), 8);
+		assert_eq!(floor.get(), 1);
+
+		// Composes one `weigh(_, 1)` and one `finish` stage per token it is given
+		macro_rules! compose_each {
+			($($n:tt)*) => {
+				compose!($(=> weigh(_, 1) => finish ${ignore($n)})*)
+			};
+		}
+
+		assert_eq!(compose_each!(x x x x x x x x x x x x x x x)(0), 15);
+	}
+
+	/// Tests the `map_or` and `map_or_else` keyword stages.
 	#[test]
-	fn test_simple() {
-		fn test(x: u16) -> f32 {
-			f32::from(x + 1)
+	fn test_map_or() {
+		fn try_parse(x: &str) -> Result<u32, &str> {
+			x.parse().map_err(|_| x)
+		}
+		fn finish(x: usize) -> usize {
+			x + 1
 		}
 
-		fn test2(x: f32) -> f32 {
-			x + 1.
+		assert_eq!(
+			pipe!("12" => try_parse => map_or(0, |x| x as usize * 2) => finish),
+			25
+		);
+		assert_eq!(
+			pipe!("ab" => try_parse => map_or(0, |x| x as usize * 2) => finish),
+			1
+		);
+		assert_eq!(
+			pipe!("12" => try_parse => map_or_else(str::len, |x| x as usize) => finish),
+			13
+		);
+		assert_eq!(
+			pipe!("abc" => try_parse => map_or_else(str::len, |x| x as usize) => finish),
+			4
+		);
+		assert_eq!(
+			pipe!("abc" => try_parse => ok => map_or_else(|| 7, |x| x as usize)),
+			7
+		);
+	}
+
+	/// Tests the `scan` keyword stage.
+	#[test]
+	fn test_scan() {
+		extern crate std;
+		use std::vec::Vec;
+
+		assert_eq!(
+			pipe!(1..=5 => scan(0, |acc, x| { *acc += x; Some(*acc) }) => _.collect::<Vec<_>>()),
+			[1, 3, 6, 10, 15]
+		);
+		assert_eq!(
+			pipe!(1..=5 => scan(1, |acc, x| { *acc *= x; (*acc < 10).then_some(*acc) }) => count),
+			3
+		);
+	}
+
+	/// Tests the `skip_if_default` keyword stage.
+	#[test]
+	fn test_skip_if_default() {
+		extern crate std;
+		use core::cell::Cell;
+		use std::string::String;
+
+		fn compute(x: u32) -> u32 {
+			x.saturating_sub(10)
 		}
 
-		let x = 3;
+		let calls = Cell::new(0);
+		let expensive = |x: u32| {
+			calls.set(calls.get() + 1);
+			f64::from(x).sqrt()
+		};
 
-		assert_eq!(pipe!(x+1 => test => test2), test2(test(x + 1)));
-		assert_eq!(pipe!(x+1 => test() => test2()), test2(test(x + 1)));
+		assert_eq!(
+			pipe!(26 => compute => skip_if_default(-1.0) => expensive),
+			4.0
+		);
+		assert_eq!(calls.get(), 1);
+		assert_eq!(
+			pipe!(5 => compute => skip_if_default(-1.0) => expensive),
+			-1.0
+		);
+		assert_eq!(calls.get(), 1);
+		assert_eq!(
+			pipe!("" => str::trim => skip_if_default(None) => skip_if_default(Some(0)) => str::len => Some),
+			None
+		);
+		assert_eq!(
+			pipe!(String::from(" a ").as_str() => skip_if_default("x") => str::trim),
+			"a"
+		);
 	}
 
-	/// Tests piping one argument into a function which accepts multiple arguments.
+	/// Tests the `append` keyword stage.
 	#[test]
-	fn test_underscore_fill() {
-		fn test(x: u16) -> u16 {
-			x
+	fn test_append() {
+		fn f(a: u32, b: u32, x: u32) -> u32 {
+			a * 100 + b * 10 + x
 		}
-		fn test2(x: u16, y: u16) -> (u16, u16) {
-			(x, y)
+		fn inc(x: u32) -> u32 {
+			x + 1
 		}
 
-		let x = 3;
-		let y = 4;
+		let a = 1;
+
+		assert_eq!(pipe!(3 => append f(a, 2)), pipe!(3 => f(a, 2, _)));
+		assert_eq!(pipe!(3 => append f(a, 2) => append u32::max(200)), 200);
+		assert_eq!(pipe!(3 => append inc() => append inc()), 5);
+		assert_eq!(pipe!(3 => append u32::pow(2)), 8);
+	}
+
+	/// Tests the `try_into` and `try_from` keyword stages.
+	#[test]
+	fn test_try_into() {
+		use core::num::TryFromIntError;
+
+		fn compute(x: u32) -> u32 {
+			x * 100
+		}
+		fn use_it(x: u8) -> u8 {
+			x / 2
+		}
+		fn narrow(x: u32) -> Result<u8, TryFromIntError> {
+			Ok(pipe!(x => compute => try_into? => use_it))
+		}
+		fn narrow_from(x: u32) -> Result<u8, TryFromIntError> {
+			Ok(pipe!(x => compute => try_from::<u8>? => use_it))
+		}
+
+		assert_eq!(narrow(2), Ok(100));
+		assert!(narrow(3).is_err());
+		assert_eq!(narrow_from(2), Ok(100));
+		assert!(narrow_from(3).is_err());
+		assert_eq!(pipe!(2 => compute => try_into()), Ok(200u8));
+		assert!(pipe!(-1i32 => try_from::<u32>).is_err());
+	}
+
+	/// Make sure `catch` turns a panicking stage into an `Err` and a normal one into `Ok`.
+	#[cfg(feature = "std")]
+	#[test]
+	fn test_catch() {
+		extern crate std;
+		use std::string::String;
+
+		fn compute(x: u32) -> u32 {
+			x * 2
+		}
+		fn untrusted(x: u32) -> u32 {
+			assert!(x < 10, "too big");
+			x + 1
+		}
+		fn handle_result<E>(x: Result<u32, E>) -> u32 {
+			x.unwrap_or(0)
+		}
+
+		assert_eq!(pipe!(3 => compute => catch untrusted => handle_result), 7);
+		assert_eq!(pipe!(30 => compute => catch untrusted => handle_result), 0);
+
+		let err = pipe!(30 => catch compute => unwrap => catch untrusted(_)).unwrap_err();
+		assert_eq!(err.downcast_ref::<&str>(), Some(&"too big"));
+
+		fn add_len(x: u32, s: &str) -> u32 {
+			x + s.len() as u32
+		}
+		let word = String::from("abc");
+		assert_eq!(pipe!(1 => catch add_len(_, &word) => handle_result), 4);
+		assert_eq!(word, "abc");
+	}
+
+	/// Make sure a function named `catch` isn't mistaken for the modifier.
+	#[test]
+	fn test_catch_fn() {
+		fn catch(x: u32) -> u32 {
+			x + 1
+		}
+
+		assert_eq!(pipe!(1 => catch => catch), 3);
+		assert_eq!(pipe!(1 => catch() => catch(_)), 3);
+	}
+
+	/// Tests the `inspect_each` keyword stage.
+	#[test]
+	fn test_inspect_each() {
+		extern crate std;
+		use std::vec::Vec;
+
+		let mut seen = Vec::new();
+		let doubled = pipe!(1..=5 => inspect_each(|x| seen.push(*x)) => _.map(|x| x * 2) => _.take(3) => _.collect::<Vec<_>>());
+
+		assert_eq!(doubled, [2, 4, 6]);
+		assert_eq!(seen, [1, 2, 3]);
+
+		let mut calls = 0;
+		let lazy = pipe!([1, 2] => _.into_iter() => inspect_each(|_| calls += 1));
+		drop(lazy);
+		assert_eq!(calls, 0);
+	}
+
+	/// Tests the `chain` keyword stage.
+	#[test]
+	fn test_chain() {
+		extern crate std;
+		use std::vec::Vec;
+
+		let second = 7..9;
 
-		assert_eq!(pipe!(x-1 => test => test2(_, y)), test2(test(x - 1), y));
-		assert_eq!(pipe!(x-1 => test => test2(y, _)), test2(y, test(x - 1),));
 		assert_eq!(
-			pipe!(x-1 => test => test2(_, _)),
-			test2(test(x - 1), test(x - 1))
+			pipe!(1..4 => chain(second) => _.collect::<Vec<_>>()),
+			[1, 2, 3, 7, 8]
+		);
+		assert_eq!(
+			pipe!(1..4 => chain([10, 20]) => chain(Some(30)) => sum::<u32>()),
+			66
 		);
 	}
 
-	/// Make sure associated functions are callable.
+	/// Make sure constructors can be passed as the function of mapping stages.
 	#[test]
-	fn test_associated_functions() {
-		fn test(x: u16) -> u16 {
-			x + 1
+	fn test_map_constructors() {
+		extern crate std;
+		use std::vec::Vec;
+
+		#[derive(Debug, PartialEq)]
+		enum Reading {
+			Raw(u32),
 		}
 
-		let x = 3;
+		fn prep(x: u32) -> core::ops::Range<u32> {
+			0..x
+		}
 
 		assert_eq!(
-			pipe!(x-2 => test => u16::to_be => u16::isqrt),
-			u16::isqrt(u16::to_be(test(x - 2)))
+			pipe!(3 => prep => map(Some) => _.collect::<Vec<Option<u32>>>()),
+			[Some(0), Some(1), Some(2)]
+		);
+		assert_eq!(
+			pipe!(2 => prep => _.map(Reading::Raw) => _.collect::<Vec<_>>()),
+			[Reading::Raw(0), Reading::Raw(1)]
 		);
+		assert_eq!(
+			pipe!(2 => prep => map(Ok::<_, ()>) => _.collect::<Result<Vec<_>, _>>()),
+			Ok([0, 1].into())
+		);
+		assert_eq!(pipe!(Some(4) => map(Reading::Raw)), Some(Reading::Raw(4)));
 	}
 
-	/// Make sure we can pipe into methods.
+	/// Tests the `push_str_mut` and `push_char_mut` keyword stages.
+	#[cfg(feature = "alloc")]
 	#[test]
-	fn test_methods() {
-		fn test(x: u16) -> u16 {
-			x + 1
+	fn test_push_str_mut() {
+		extern crate std;
+		use std::string::String;
+
+		fn finish(x: String) -> usize {
+			x.len()
 		}
 
-		let x = 3;
-		let y = 4;
 		assert_eq!(
-			pipe!(x+2 => test => y.max => test),
-			test(y.max(test(x + 2)))
+			pipe!(String::new() => push_str_mut("a") => push_str_mut("b") => push_char_mut('c')),
+			"abc"
 		);
+		assert_eq!(pipe!(String::from("x") => push_char_mut('é') => finish), 3);
+	}
+
+	/// Make sure `push_str_mut` and `push_char_mut` are ordinary function stages without the `alloc` feature.
+	#[cfg(not(feature = "alloc"))]
+	#[test]
+	fn test_push_str_mut_without_feature() {
+		fn push_str_mut(s: &str) -> impl Fn(usize) -> usize {
+			move |x| x + s.len()
+		}
+		fn push_char_mut(c: char) -> impl Fn(usize) -> usize {
+			move |x| x + c.len_utf8()
+		}
+
+		assert_eq!(pipe!(1 => push_str_mut("ab") => push_char_mut('é')), 5);
+	}
+
+	/// Tests the `try_fold` keyword stage.
+	#[test]
+	fn test_try_fold() {
+		fn prep(x: u8) -> core::ops::RangeInclusive<u8> {
+			1..=x
+		}
+		fn handle(x: Result<u8, &str>) -> u8 {
+			x.unwrap_or(0)
+		}
 
-		let mut y = None;
 		assert_eq!(
-			pipe!(x+2 => y.map_or(_, |x| x + 2) => test),
-			test(y.map_or(x + 2, |x| x + 2))
+			pipe!(10 => prep => try_fold(0u8, |acc, x| acc.checked_add(x).ok_or("overflow")) => handle),
+			55
+		);
+		assert_eq!(
+			pipe!(30 => prep => try_fold(0u8, |acc, x| acc.checked_add(x).ok_or("overflow"))),
+			Err("overflow")
+		);
+		assert_eq!(pipe!(30 => prep => try_fold(1u8, u8::checked_mul)), None);
+	}
+
+	/// Tests the `max_by_key`, `min_by_key`, `max_by` and `min_by` keyword stages.
+	#[test]
+	fn test_max_min_by() {
+		#[derive(Clone, Copy, Debug, PartialEq)]
+		struct Task {
+			name: &'static str,
+			priority: u8,
+		}
+
+		fn prep(x: &[Task]) -> core::slice::Iter<'_, Task> {
+			x.iter()
+		}
+		fn handle_option(x: Option<&Task>) -> &'static str {
+			x.map_or("none", |t| t.name)
+		}
+
+		let tasks = [
+			Task {
+				name: "a",
+				priority: 2,
+			},
+			Task {
+				name: "b",
+				priority: 5,
+			},
+			Task {
+				name: "c",
+				priority: 1,
+			},
+		];
+
+		assert_eq!(
+			pipe!(&tasks => prep => max_by_key(|t| t.priority) => handle_option),
+			"b"
+		);
+		assert_eq!(
+			pipe!(&tasks => prep => min_by_key(|t| t.priority) => handle_option),
+			"c"
+		);
+		assert_eq!(
+			pipe!(&tasks => prep => max_by(|a, b| a.name.cmp(b.name)) => handle_option),
+			"c"
+		);
+		assert_eq!(
+			pipe!(&tasks => prep => min_by(|a, b| a.name.cmp(b.name)) => handle_option),
+			"a"
 		);
+		assert_eq!(
+			pipe!(&tasks[..0] => prep => max_by_key(|t| t.priority) => handle_option),
+			"none"
+		);
+	}
 
-		y = Some(x + 2);
+	/// Tests the `borrow_rest` keyword stage.
+	#[test]
+	fn test_borrow_rest() {
+		extern crate std;
+		use std::{string::String, vec::Vec};
+
+		fn build(x: &str) -> Vec<String> {
+			x.split(',').map(String::from).collect()
+		}
+		fn done(x: usize) -> usize {
+			x * 10
+		}
+
+		let v = build("a,b,c");
+		assert_eq!(pipe!(v => borrow_rest => _.len() => done), 30);
 		assert_eq!(
-			pipe!(x+2 => y.map_or(_, |x| x + 2) => test),
-			test(y.map_or(x + 2, |x| x + 2))
+			pipe!(v => borrow_rest => _.first() => cloned),
+			Some(String::from("a"))
 		);
+		assert_eq!(v.concat(), "abc");
+
+		assert_eq!(pipe!("x,y" => build => borrow_rest => Vec::len), 2);
+		assert_eq!(pipe!(v => borrow_rest() => _.len()), 3);
 	}
 
-	/// Make sure we can pipe into a lambda.
+	/// Tests the `filter_opt` keyword stage.
 	#[test]
-	fn test_lambdas() {
-		fn test(x: u16) -> u16 {
+	fn test_filter_opt() {
+		fn lookup(x: i32) -> Option<i32> {
+			(x != 0).then_some(x - 10)
+		}
+
+		assert_eq!(pipe!(15 => lookup => filter_opt(|v| *v > 0)), Some(5));
+		assert_eq!(pipe!(5 => lookup => filter_opt(|v| *v > 0)), None);
+		assert_eq!(
+			pipe!(5 => lookup => filter_opt(|v| *v > 0) => Option::unwrap_or_default),
+			0
+		);
+		assert_eq!(pipe!(0 => lookup => filter_opt(|_| true)), None);
+	}
+
+	/// Tests the `cast` keyword stage.
+	#[cfg(feature = "num-traits")]
+	#[test]
+	fn test_cast() {
+		fn compute(x: i32) -> i32 {
+			x * 1000
+		}
+		fn finish(x: Option<i16>) -> i16 {
+			x.unwrap_or(i16::MAX)
+		}
+
+		assert_eq!(pipe!(3 => compute => cast::<i16>() => finish), 3000);
+		assert_eq!(pipe!(300 => compute => cast::<i16>() => finish), i16::MAX);
+		assert_eq!(pipe!(-1 => compute => cast::<u32>), None);
+		assert_eq!(pipe!(2.5f64 => cast::<u8>), Some(2));
+	}
+
+	/// Make sure `cast::<T>` is an ordinary function stage without the `num-traits` feature.
+	#[cfg(not(feature = "num-traits"))]
+	#[test]
+	fn test_cast_without_feature() {
+		fn cast<T: From<u8>>(x: u8) -> T {
+			T::from(x)
+		}
+
+		assert_eq!(pipe!(3 => cast::<u32> => _.pow(2)), 9u32);
+		assert_eq!(pipe!(3 => cast::<u64>()), 3u64);
+	}
+
+	/// Make sure `pipe_array!` pipes each element and keeps the array length.
+	#[test]
+	fn test_pipe_array() {
+		fn double(x: u8) -> u8 {
+			x * 2
+		}
+		fn increment(x: u8) -> u8 {
 			x + 1
 		}
 
+		let arr: [u8; 4] = [1, 2, 3, 4];
+		let y = 5;
+
+		let out: [u8; 4] = pipe_array!(arr => double => increment);
+		assert_eq!(out, [3, 5, 7, 9]);
+		assert_eq!(pipe_array!([1, 2] => double => y.max(_)), [5, 5]);
+	}
+
+	/// Make sure function pointers and boxed closures stored in variables can be used as stages.
+	#[test]
+	fn test_fn_variables() {
+		extern crate std;
+		use std::boxed::Box;
+
+		fn double(x: u32) -> u32 {
+			x * 2
+		}
+
+		let f: fn(u32) -> u32 = double;
+		let g: Box<dyn Fn(u32) -> u32> = Box::new(|x| x + 1);
+		let h: Box<dyn Fn(u32, u32) -> u32> = Box::new(|x, y| x * y);
 		let x = 3;
 
+		assert_eq!(pipe!(x => f => g), 7);
+		assert_eq!(pipe!(x => f() => (g) => f), 14);
+		assert_eq!(pipe!(x => (g) => (h)(_, 3)), 12);
+	}
+
+	/// Make sure the result of a factory returning `impl Fn` can be called as a stage.
+	#[test]
+	fn test_factory_stages() {
+		fn make_adder(n: u32) -> impl Fn(u32) -> u32 {
+			move |x| x + n
+		}
+		fn make_scaler(n: u32) -> impl Fn(u32, u32) -> u32 {
+			move |x, y| x * y * n
+		}
+		fn finish(x: u32) -> u32 {
+			x * 10
+		}
+
+		let offset = 2;
+
+		assert_eq!(pipe!(1 => (make_adder(5)) => finish), 60);
+		assert_eq!(pipe!(1 => (make_adder(offset + 1)) => (make_adder(1))), 5);
+		assert_eq!(pipe!(1 => (make_adder(5)) => (make_scaler(2))(_, 3)), 36);
+	}
+
+	/// Make sure closures stored in struct fields can be called as stages.
+	#[test]
+	fn test_field_closures() {
+		struct Pipeline<F> {
+			transform: F,
+			combine: fn(u32, u32) -> u32,
+		}
+		impl<F: Fn(u32) -> u32> Pipeline<F> {
+			fn run(&self, x: u32) -> u32 {
+				pipe!(x => (self.transform) => (self.transform)(_) => (self.combine)(_, 2))
+			}
+		}
+
+		let offset = 1;
+		let p = Pipeline {
+			transform: |x| x + offset,
+			combine: |x, y| x * y,
+		};
+
+		assert_eq!(p.run(3), 10);
+		assert_eq!(pipe!(3 => (p.combine)(_, _) => (p.transform)), 10);
+	}
+
+	/// Make sure long pipelines of stages with blanks or blank receivers stay within the default recursion limit.
+	#[test]
+	fn test_long_pipelines() {
+		fn add(x: u32, y: u32) -> u32 {
+			x + y
+		}
+
+		// Pipes through one `add(_, 1)` stage per token it is given
+		macro_rules! add_each {
+			($($n:tt)*) => {
+				pipe!(0 $(=> add(_, 1) ${ignore($n)})*)
+			};
+		}
+
 		assert_eq!(
-			pipe!(x => test => |x| {x - 2} => test),
-			test((|x| { x - 2 })(test(x)))
+			add_each!(x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x),
+			50
+		);
+
+		// Pipes through one `_.wrapping_add(1)` stage per token it is given
+		macro_rules! wrapping_add_each {
+			($($n:tt)*) => {
+				pipe!(0u32 $(=> _.wrapping_add(1) ${ignore($n)})*)
+			};
+		}
+
+		assert_eq!(
+			wrapping_add_each!(x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x),
+			50
+		);
+
+		fn inc(x: u32) -> Result<u32, ()> {
+			x.checked_add(1).ok_or(())
+		}
+
+		// Pipes through one `inc` stage per token it is given, unwrapping each output
+		macro_rules! try_inc_each {
+			($($n:tt)*) => {
+				pipe_try!(0 $(=> inc ${ignore($n)})*)
+			};
+		}
+
+		assert_eq!(
+			try_inc_each!(
+				x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x
+				x x x x x x x x x x x x x x x x x x x x
+			),
+			Ok(70)
 		);
 	}
 
@@ -0,0 +1,8 @@
+/// Make sure misuses of `pipe!` are rejected with helpful errors.
+#[test]
+fn ui() {
+	let t = trybuild::TestCases::new();
+	t.compile_fail("tests/ui/*.rs");
+	#[cfg(not(feature = "std"))]
+	t.compile_fail("tests/ui/no_std/*.rs");
+}
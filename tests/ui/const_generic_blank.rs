@@ -0,0 +1,13 @@
+use pipelining_macro::pipe;
+
+fn compute(x: usize) -> usize {
+	x + 1
+}
+
+fn make_array<const N: usize>() -> [u8; N] {
+	[0; N]
+}
+
+fn main() {
+	let _ = pipe!(3 => compute => make_array::<{ _ }>());
+}
@@ -0,0 +1,9 @@
+use pipelining_macro::pipe;
+
+fn compute(x: u32) -> u32 {
+	x + 1
+}
+
+fn main() {
+	let _ = pipe!(3 => catch compute);
+}
@@ -0,0 +1,9 @@
+use pipelining_macro::pipe_array;
+
+fn double(x: u8) -> u8 {
+	x * 2
+}
+
+fn main() {
+	let _ = pipe_array!(Some(3u8) => double);
+}